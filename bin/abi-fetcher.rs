@@ -1,57 +1,36 @@
+use anyhow::Result;
 use dotenv::dotenv;
-use ethabi::Contract;
-use evm_indexer::chains::chains::{get_chain, ETHEREUM};
+use ethabi::{Address, Contract};
+use ethers::abi::parse_abi;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::BlockId;
+use evm_indexer::abi_sources::etherscan::Etherscan;
+use evm_indexer::abi_sources::local::LocalDirectory;
+use evm_indexer::abi_sources::rate_limiter::RateLimiter;
+use evm_indexer::abi_sources::sourcify::Sourcify;
+use evm_indexer::abi_sources::{
+    get_contract_source, AbiSource, ContractSource, ABI_SOURCE_ETHERSCAN, ABI_SOURCE_LOCAL,
+    ABI_SOURCE_SOURCIFY,
+};
+use evm_indexer::chains::chains::{get_chain, Chain};
 use evm_indexer::configs::abi_fetcher_config::EVMAbiFetcherConfig;
 use evm_indexer::db::db::Database;
 use evm_indexer::db::models::models::{
-    DatabaseContract, DatabaseContractInformation, DatabaseMethod,
+    DatabaseContract, DatabaseContractImplementation, DatabaseContractInformation,
+    DatabaseContractSource, DatabaseError, DatabaseEvent, DatabaseMethod,
 };
+use evm_indexer::rpc::proxies::{get_implementation, PROXY_STANDARD_EXPLORER};
+use evm_indexer::utils::format_address;
+use futures::{future::join_all, stream, StreamExt};
 use log::LevelFilter;
 use log::*;
+use rand::seq::SliceRandom;
 use reqwest::Client;
-use serde::Deserialize;
-use serde::Serialize;
-use serde_json::Error;
 use simple_logger::SimpleLogger;
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Response {
-    pub status: String,
-    pub message: String,
-    pub result: Vec<ContractDataResult>,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ContractDataResult {
-    #[serde(rename = "SourceCode")]
-    pub source_code: String,
-    #[serde(rename = "ABI")]
-    pub abi: String,
-    #[serde(rename = "ContractName")]
-    pub contract_name: String,
-    #[serde(rename = "CompilerVersion")]
-    pub compiler_version: String,
-    #[serde(rename = "OptimizationUsed")]
-    pub optimization_used: String,
-    #[serde(rename = "Runs")]
-    pub runs: String,
-    #[serde(rename = "ConstructorArguments")]
-    pub constructor_arguments: String,
-    #[serde(rename = "EVMVersion")]
-    pub evmversion: String,
-    #[serde(rename = "Library")]
-    pub library: String,
-    #[serde(rename = "LicenseType")]
-    pub license_type: String,
-    #[serde(rename = "Proxy")]
-    pub proxy: String,
-    #[serde(rename = "Implementation")]
-    pub implementation: String,
-    #[serde(rename = "SwarmSource")]
-    pub swarm_source: String,
-}
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
 
 #[tokio::main()]
 async fn main() {
@@ -69,153 +48,570 @@ async fn main() {
 
     info!("Starting EVM ABI fetcher");
 
-    let db = Database::new(config.db_url.clone(), config.redis_url.clone(), ETHEREUM)
+    if let Some(path) = &config.import_signatures {
+        // Signatures are shared by every chain.
+        let db = Database::new(
+            config.db_url.clone(),
+            config.redis_url.clone(),
+            config.chains[0],
+        )
+        .await
+        .expect("Unable to start DB connection.");
+
+        import_signatures(&db, path).await;
+        return;
+    }
+
+    let client = Client::new();
+
+    let abi_sources = get_abi_sources(&config, &client);
+
+    let mut workers = vec![];
+
+    for (chain, sources) in abi_sources {
+        if sources.is_empty() {
+            continue;
+        }
+
+        info!("Starting ABI fetcher for chain {}.", chain);
+
+        // Each chain uses its own connection, the contracts are selected for its chain.
+        let db = Database::new(
+            config.db_url.clone(),
+            config.redis_url.clone(),
+            get_chain(chain),
+        )
         .await
         .expect("Unable to start DB connection.");
 
+        workers.push(tokio::spawn(fetch_chain(
+            db,
+            sources,
+            config.workers,
+            config.unverified_recheck_hours,
+        )));
+    }
+
+    join_all(workers).await;
+}
+
+/// Contract fetched from the ABI sources with its implementation when it is a proxy.
+struct FetchedContract {
+    contract: DatabaseContract,
+    contracts_information: Vec<DatabaseContractInformation>,
+    contracts_source: Vec<DatabaseContractSource>,
+    implementation: Option<DatabaseContractImplementation>,
+}
+
+async fn fetch_chain(
+    db: Database,
+    sources: Vec<Box<dyn AbiSource>>,
+    workers: usize,
+    unverified_recheck_hours: i64,
+) {
+    let chain = db.chain;
+
     loop {
+        db.update_contracts_interactions().await.unwrap();
+
         let contracts = db.get_contracts_missing_parsed().await.unwrap();
 
-        if contracts.len() > 0 {
-            info!("Fetching ABIs for {} contracts.", contracts.len());
+        if contracts.is_empty() {
+            sleep(Duration::from_secs(30)).await;
+            continue;
+        }
 
-            let client = Client::new();
+        info!(
+            "Fetching ABIs for {} contracts of chain {}.",
+            contracts.len(),
+            chain.name
+        );
 
-            let mut contracts_fetched: Vec<DatabaseContract> = Vec::new();
+        let results: Vec<Result<FetchedContract, DatabaseContract>> = stream::iter(contracts)
+            .map(|contract| fetch_contract(&sources, &chain, contract))
+            .buffer_unordered(workers)
+            .collect()
+            .await;
 
-            let mut contracts_information_fetched: Vec<DatabaseContractInformation> = Vec::new();
+        let mut contracts_fetched: Vec<DatabaseContract> = Vec::new();
 
-            for mut contract in contracts {
-                let uri_str: String;
+        let mut contracts_failed: Vec<DatabaseContract> = Vec::new();
 
-                let chain = get_chain(contract.chain.clone());
+        // Implementations shared by many proxies of the same batch are only stored once.
+        let mut contracts_information_fetched: HashMap<
+            (String, String),
+            DatabaseContractInformation,
+        > = HashMap::new();
 
-                let token = config.api_source_tokens.get(chain.name);
+        let mut contracts_source_fetched: HashMap<(String, String), DatabaseContractSource> =
+            HashMap::new();
 
-                match token {
-                    Some(token) => {
-                        if chain.abi_source_require_auth {
-                            uri_str = format!(
-                                "{}api?module=contract&action=getsourcecode&address={}&apikey={}",
-                                chain.abi_source_api, contract.contract, token
-                            );
-                        } else {
-                            uri_str = format!(
-                                "{}api?module=contract&action=getsourcecode&address={}",
-                                chain.abi_source_api, contract.contract
-                            );
-                        }
-                    }
-                    None => {
-                        continue;
-                    }
-                }
+        let mut implementations_fetched: Vec<DatabaseContractImplementation> = Vec::new();
 
-                let response = client.get(uri_str).send().await;
-
-                match response {
-                    Ok(data) => match data.text().await {
-                        Ok(response) => {
-                            let contract_response: Result<Response, Error> =
-                                serde_json::from_str(&response);
-
-                            let mut db_contract_information = DatabaseContractInformation {
-                                chain: chain.name.to_owned(),
-                                contract: contract.contract.clone(),
-                                abi: None,
-                                name: None,
-                                verified: false,
-                            };
-
-                            match contract_response {
-                                Ok(contract_response_formatted) => {
-                                    let result = match contract_response_formatted.result.first() {
-                                        Some(result) => result,
-                                        None => continue,
-                                    };
-
-                                    if contract_response_formatted.status == "1"
-                                        && contract_response_formatted.message == "OK"
-                                    {
-                                        if result.abi == "Contract source code not verified" {
-                                            contract.parsed = true;
-
-                                            contracts_fetched.push(contract);
-
-                                            contracts_information_fetched
-                                                .push(db_contract_information);
-                                        } else {
-                                            db_contract_information.abi = Some(result.abi.clone());
-                                            db_contract_information.name =
-                                                Some(result.contract_name.clone());
-
-                                            db_contract_information.verified = true;
-
-                                            contract.parsed = true;
-
-                                            contracts_fetched.push(contract);
-
-                                            contracts_information_fetched
-                                                .push(db_contract_information);
-                                        }
-                                    }
-                                }
-                                Err(_) => {
-                                    continue;
-                                }
-                            }
-                        }
-                        Err(_) => {
-                            continue;
-                        }
-                    },
-                    Err(_) => {
-                        continue;
-                    }
+        for result in results {
+            let fetched = match result {
+                Ok(fetched) => fetched,
+                Err(contract) => {
+                    contracts_failed.push(contract);
+                    continue;
+                }
+            };
+
+            // The proxy is the last one, replacing the implementation information when it is
+            // also the implementation of another proxy.
+            for db_contract_information in fetched.contracts_information {
+                let id = (
+                    db_contract_information.contract.clone(),
+                    db_contract_information.chain.clone(),
+                );
+
+                if db_contract_information.contract == fetched.contract.contract {
+                    contracts_information_fetched.insert(id, db_contract_information);
+                } else {
+                    contracts_information_fetched
+                        .entry(id)
+                        .or_insert(db_contract_information);
                 }
             }
 
-            let mut methods: Vec<DatabaseMethod> = Vec::new();
-
-            for contracts_information in &contracts_information_fetched {
-                let contract: Contract = match &contracts_information.abi {
-                    Some(abi) => match serde_json::from_str(abi) {
-                        Ok(contract) => contract,
-                        Err(_) => {
-                            continue;
-                        }
-                    },
-                    None => {
-                        continue;
-                    }
-                };
+            for db_contract_source in fetched.contracts_source {
+                contracts_source_fetched.insert(
+                    (
+                        db_contract_source.contract.clone(),
+                        db_contract_source.chain.clone(),
+                    ),
+                    db_contract_source,
+                );
+            }
+
+            if let Some(implementation) = fetched.implementation {
+                implementations_fetched.push(implementation);
+            }
+
+            contracts_fetched.push(fetched.contract);
+        }
 
-                let functions = contract.functions();
+        let contracts_information_fetched: Vec<DatabaseContractInformation> =
+            contracts_information_fetched.into_values().collect();
 
-                for function in functions {
-                    let signature = format!("0x{}", hex::encode(function.short_signature()));
+        let contracts_source_fetched: Vec<DatabaseContractSource> =
+            contracts_source_fetched.into_values().collect();
 
-                    let db_method = DatabaseMethod {
-                        name: function.name.clone(),
-                        method: signature,
-                    };
+        let mut signatures = Signatures::default();
 
-                    methods.push(db_method);
+        for contracts_information in &contracts_information_fetched {
+            let contract: Contract = match &contracts_information.abi {
+                Some(abi) => match serde_json::from_str(abi) {
+                    Ok(contract) => contract,
+                    Err(_) => {
+                        continue;
+                    }
+                },
+                None => {
+                    continue;
                 }
-            }
+            };
 
+            signatures.add_contract(&contract);
+        }
+
+        if !contracts_fetched.is_empty() {
             db.update_contracts(&contracts_fetched).await.unwrap();
+
+            db.update_contracts_fetched(&contracts_fetched, unverified_recheck_hours)
+                .await
+                .unwrap();
+
             db.store_contracts_information(&contracts_information_fetched)
                 .await
                 .unwrap();
-            db.store_methods(&methods).await.unwrap();
+        }
 
-            info!(
-                "Stored {} ABIs from {} contracts with {} methods.",
-                contracts_information_fetched.len(),
-                contracts_fetched.len(),
-                methods.len()
+        if !contracts_failed.is_empty() {
+            db.update_contracts_failed(&contracts_failed).await.unwrap();
+        }
+
+        if !contracts_source_fetched.is_empty() {
+            db.store_contracts_source(&contracts_source_fetched)
+                .await
+                .unwrap();
+        }
+
+        if !implementations_fetched.is_empty() {
+            db.store_contracts_implementations(&implementations_fetched)
+                .await
+                .unwrap();
+        }
+
+        let (methods, events, errors) = signatures.store(&db).await;
+
+        info!(
+            "Stored {} ABIs and {} sources from {} contracts of chain {} with {} proxies {} methods {} events and {} errors. {} contracts failed.",
+            contracts_information_fetched.len(),
+            contracts_source_fetched.len(),
+            contracts_fetched.len(),
+            chain.name,
+            implementations_fetched.len(),
+            methods,
+            events,
+            errors,
+            contracts_failed.len()
+        );
+    }
+}
+
+/// Fetches the ABI of a contract and the ABI of its implementation when it is a proxy. The
+/// contract is returned as an error when any source failed, to be fetched again later.
+async fn fetch_contract(
+    sources: &[Box<dyn AbiSource>],
+    chain: &Chain,
+    mut contract: DatabaseContract,
+) -> Result<FetchedContract, DatabaseContract> {
+    let source = match get_contract_source(sources, chain, &contract.contract).await {
+        Ok(source) => source,
+        Err(_) => return Err(contract),
+    };
+
+    let mut contracts_information = Vec::new();
+
+    let mut contracts_source: Vec<DatabaseContractSource> =
+        to_contract_source(chain, &contract.contract, &source)
+            .into_iter()
+            .collect();
+
+    let mut db_contract_information = to_contract_information(chain, &contract.contract, &source);
+
+    let implementation = match get_proxy_implementation(chain, &contract.contract, &source).await {
+        Ok(implementation) => implementation,
+        Err(_) => return Err(contract),
+    };
+
+    if let Some(db_implementation) = &implementation {
+        db_contract_information.implementation = Some(db_implementation.implementation.clone());
+
+        let implementation_source =
+            match get_contract_source(sources, chain, &db_implementation.implementation).await {
+                Ok(implementation_source) => implementation_source,
+                Err(_) => return Err(contract),
+            };
+
+        let db_implementation_information = to_contract_information(
+            chain,
+            &db_implementation.implementation,
+            &implementation_source,
+        );
+
+        if let Some(db_implementation_source) = to_contract_source(
+            chain,
+            &db_implementation.implementation,
+            &implementation_source,
+        ) {
+            contracts_source.push(db_implementation_source);
+        }
+
+        db_contract_information.abi = merge_abis(
+            &db_contract_information.abi,
+            &db_implementation_information.abi,
+        );
+
+        contracts_information.push(db_implementation_information);
+    }
+
+    contract.parsed = true;
+
+    contract.verified = db_contract_information.abi.is_some();
+
+    contracts_information.push(db_contract_information);
+
+    Ok(FetchedContract {
+        contract,
+        contracts_information,
+        contracts_source,
+        implementation,
+    })
+}
+
+/// Methods, events and errors signatures deduplicated by their primary keys, as the same
+/// signature is usually repeated across many ABIs of a single batch.
+#[derive(Default)]
+struct Signatures {
+    methods: HashMap<String, DatabaseMethod>,
+    events: HashMap<(String, Vec<bool>), DatabaseEvent>,
+    errors: HashMap<(String, String), DatabaseError>,
+}
+
+impl Signatures {
+    fn add_contract(&mut self, contract: &Contract) {
+        for function in contract.functions() {
+            self.add_method(DatabaseMethod::from_abi(function));
+        }
+
+        // Anonymous events don't have a topic to be identified with.
+        for event in contract.events().filter(|event| !event.anonymous) {
+            let db_event = DatabaseEvent::from_abi(event);
+
+            self.events
+                .insert((db_event.topic.clone(), db_event.indexed.clone()), db_event);
+        }
+
+        for error in contract.errors() {
+            let db_error = DatabaseError::from_abi(error);
+
+            self.errors.insert(
+                (db_error.selector.clone(), db_error.signature.clone()),
+                db_error,
             );
         }
     }
+
+    fn add_method(&mut self, method: DatabaseMethod) {
+        self.methods.insert(method.method.clone(), method);
+    }
+
+    async fn store(self, db: &Database) -> (usize, usize, usize) {
+        let methods: Vec<DatabaseMethod> = self.methods.into_values().collect();
+        let events: Vec<DatabaseEvent> = self.events.into_values().collect();
+        let errors: Vec<DatabaseError> = self.errors.into_values().collect();
+
+        if !methods.is_empty() {
+            db.store_methods(&methods).await.unwrap();
+        }
+
+        if !events.is_empty() {
+            db.store_events(&events).await.unwrap();
+        }
+
+        if !errors.is_empty() {
+            db.store_errors(&errors).await.unwrap();
+        }
+
+        (methods.len(), events.len(), errors.len())
+    }
+}
+
+/// Imports a signatures database from a local file. The file can be a JSON ABI or a list of
+/// human readable ABI items (`function ...`, `event ...`, `error ...`), one per line. Lines
+/// without a prefix are read as plain method signatures like `transfer(address,uint256)`.
+async fn import_signatures(db: &Database, path: &str) {
+    info!("Importing signatures from {}.", path);
+
+    let file = std::fs::read_to_string(path).expect("Unable to read signatures file.");
+
+    let mut signatures = Signatures::default();
+
+    let mut skipped = 0;
+
+    if file.trim_start().starts_with('[') {
+        let contract: Contract = serde_json::from_str(&file).expect("Unable to parse JSON ABI.");
+
+        signatures.add_contract(&contract);
+    } else {
+        for line in file.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+
+            if line.starts_with("function ")
+                || line.starts_with("event ")
+                || line.starts_with("error ")
+            {
+                match parse_abi(&[line]) {
+                    Ok(contract) => signatures.add_contract(&contract),
+                    Err(_) => skipped += 1,
+                }
+            } else {
+                match DatabaseMethod::from_signature(line) {
+                    Some(method) => signatures.add_method(method),
+                    None => skipped += 1,
+                }
+            }
+        }
+    }
+
+    let (methods, events, errors) = signatures.store(db).await;
+
+    info!(
+        "Imported {} methods {} events and {} errors. Skipped {} invalid lines.",
+        methods, events, errors, skipped
+    );
+}
+
+/// Builds the ABI sources of each chain in the configured priority order, the explorer is only
+/// used for chains with a token and the local directory only when it is configured.
+fn get_abi_sources(
+    config: &EVMAbiFetcherConfig,
+    client: &Client,
+) -> HashMap<String, Vec<Box<dyn AbiSource>>> {
+    let mut abi_sources: HashMap<String, Vec<Box<dyn AbiSource>>> = HashMap::new();
+
+    for (chain, names) in config.abi_sources.iter() {
+        let mut sources: Vec<Box<dyn AbiSource>> = Vec::new();
+
+        for name in names {
+            match name.as_str() {
+                ABI_SOURCE_ETHERSCAN => {
+                    if let Some(token) = config.api_source_tokens.get(chain) {
+                        sources.push(Box::new(Etherscan {
+                            client: client.clone(),
+                            limiter: Arc::new(RateLimiter::new(config.explorer_rate_limit)),
+                            token: token.clone(),
+                        }));
+                    }
+                }
+                ABI_SOURCE_SOURCIFY => sources.push(Box::new(Sourcify {
+                    client: client.clone(),
+                    url: config.sourcify_url.clone(),
+                })),
+                ABI_SOURCE_LOCAL => {
+                    if let Some(path) = &config.abis_directory {
+                        sources.push(Box::new(LocalDirectory { path: path.clone() }));
+                    }
+                }
+                _ => warn!("Unknown ABI source {} for chain {}.", name, chain),
+            }
+        }
+
+        abi_sources.insert(chain.clone(), sources);
+    }
+
+    abi_sources
+}
+
+fn to_contract_information(
+    chain: &Chain,
+    contract: &str,
+    source: &Option<(ContractSource, &'static str)>,
+) -> DatabaseContractInformation {
+    match source {
+        Some((source, _)) => DatabaseContractInformation {
+            chain: chain.name.to_owned(),
+            contract: contract.to_owned(),
+            abi: Some(source.abi.clone()),
+            name: source.name.clone(),
+            verified: true,
+            implementation: None,
+            inferred: false,
+        },
+        None => DatabaseContractInformation {
+            chain: chain.name.to_owned(),
+            contract: contract.to_owned(),
+            abi: None,
+            name: None,
+            verified: false,
+            implementation: None,
+            inferred: false,
+        },
+    }
+}
+
+/// Verified source code of a contract, the constructor arguments are decoded with the ABI of
+/// the contract itself and not the ABI merged with its implementation.
+fn to_contract_source(
+    chain: &Chain,
+    contract: &str,
+    source: &Option<(ContractSource, &'static str)>,
+) -> Option<DatabaseContractSource> {
+    let (source, name) = source.as_ref()?;
+
+    let source_code = source.source_code.as_ref()?;
+
+    Some(DatabaseContractSource::from_source_code(
+        chain.name,
+        contract,
+        &Some(source.abi.clone()),
+        name,
+        source_code,
+    ))
+}
+
+/// Reads the implementation from the proxy storage at the latest block, falling back to the
+/// implementation reported by the block explorer. RPC errors are returned for the contract to
+/// be fetched again later.
+async fn get_proxy_implementation(
+    chain: &Chain,
+    address: &str,
+    source: &Option<(ContractSource, &'static str)>,
+) -> Result<Option<DatabaseContractImplementation>> {
+    let explorer_implementation = |block: i64| {
+        source
+            .as_ref()
+            .and_then(|(source, _)| source.implementation.clone())
+            .map(|implementation| DatabaseContractImplementation {
+                block,
+                chain: chain.name.to_owned(),
+                contract: address.to_owned(),
+                implementation,
+                standard: PROXY_STANDARD_EXPLORER.to_owned(),
+            })
+    };
+
+    let rpcs = chain.get_rpcs();
+
+    let provider = rpcs
+        .choose(&mut rand::thread_rng())
+        .and_then(|rpc| Provider::<Http>::try_from(rpc.as_str()).ok());
+
+    // Without a provider the explorer is the only source of the implementation, at an
+    // unknown block.
+    let (provider, proxy) = match (provider, address.parse::<Address>()) {
+        (Some(provider), Ok(proxy)) => (provider, proxy),
+        _ => return Ok(explorer_implementation(0)),
+    };
+
+    let block = provider.get_block_number().await?;
+
+    let detected = get_implementation(&provider, proxy, Some(BlockId::from(block))).await?;
+
+    match detected {
+        Some((implementation, standard)) => Ok(Some(DatabaseContractImplementation {
+            block: block.as_u64() as i64,
+            chain: chain.name.to_owned(),
+            contract: address.to_owned(),
+            implementation: format_address(implementation),
+            standard: standard.to_owned(),
+        })),
+        None => Ok(explorer_implementation(block.as_u64() as i64)),
+    }
+}
+
+/// Appends the implementation functions, events and errors missing in the proxy ABI, the
+/// constructor, fallback and receive of the implementation are never called through the proxy.
+fn merge_abis(proxy: &Option<String>, implementation: &Option<String>) -> Option<String> {
+    let implementation: Vec<serde_json::Value> = match implementation {
+        Some(abi) => serde_json::from_str(abi).ok()?,
+        None => return proxy.clone(),
+    };
+
+    let mut merged: Vec<serde_json::Value> = match proxy {
+        Some(abi) => serde_json::from_str(abi).unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    let key = |item: &serde_json::Value| {
+        let inputs: Vec<String> = item["inputs"]
+            .as_array()
+            .map(|inputs| {
+                inputs
+                    .iter()
+                    .map(|input| input["type"].to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        format!("{}:{}({})", item["type"], item["name"], inputs.join(","))
+    };
+
+    let keys: HashSet<String> = merged.iter().map(key).collect();
+
+    for item in implementation {
+        let kind = item["type"].as_str().unwrap_or_default();
+
+        if !["function", "event", "error"].contains(&kind) || keys.contains(&key(&item)) {
+            continue;
+        }
+
+        merged.push(item);
+    }
+
+    serde_json::to_string(&merged).ok()
 }
@@ -1,4 +1,6 @@
 use dotenv::dotenv;
+use ethabi::{AbiError, Contract};
+use ethers::abi::ErrorExt;
 use evm_indexer::{
     chains::chains::Chain,
     configs::indexer_config::EVMIndexerConfig,
@@ -6,7 +8,7 @@ use evm_indexer::{
         db::Database,
         models::models::{
             DatabaseBlock, DatabaseChainIndexedState, DatabaseContract, DatabaseLog,
-            DatabaseReceipt, DatabaseTransaction,
+            DatabaseReceipt, DatabaseRevertReason, DatabaseTransaction,
         },
     },
     rpc::rpc::Rpc,
@@ -14,7 +16,11 @@ use evm_indexer::{
 use futures::future::join_all;
 use log::*;
 use simple_logger::SimpleLogger;
-use std::{collections::HashSet, thread::sleep, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    thread::sleep,
+    time::Duration,
+};
 
 #[tokio::main()]
 async fn main() {
@@ -128,6 +134,10 @@ async fn sync_chain(
         )
         .await;
 
+        if config.revert_reasons {
+            store_revert_reasons(rpc, db, &db_transactions, &db_receipts).await;
+        }
+
         for block in db_blocks.into_iter() {
             indexed_blocks.insert(block.number);
         }
@@ -232,3 +242,134 @@ async fn fetch_block(
         None => return None,
     }
 }
+
+async fn store_revert_reasons(
+    rpc: &Rpc,
+    db: &Database,
+    transactions: &[DatabaseTransaction],
+    receipts: &[DatabaseReceipt],
+) {
+    let failed: HashSet<&String> = receipts
+        .iter()
+        .filter(|receipt| receipt.status == "0")
+        .map(|receipt| &receipt.hash)
+        .collect();
+
+    let failed_transactions: Vec<&DatabaseTransaction> = transactions
+        .iter()
+        .filter(|transaction| failed.contains(&transaction.hash))
+        .collect();
+
+    if failed_transactions.is_empty() {
+        return;
+    }
+
+    let mut work = vec![];
+
+    for transaction in failed_transactions.iter() {
+        work.push(rpc.get_revert_data(transaction))
+    }
+
+    let results = join_all(work).await;
+
+    let mut reverts: Vec<(&DatabaseTransaction, Vec<u8>, Option<String>)> = Vec::new();
+
+    for (transaction, result) in failed_transactions.into_iter().zip(results) {
+        match result {
+            Ok(Some((data, message))) => reverts.push((transaction, data, message)),
+            _ => continue,
+        }
+    }
+
+    let errors = get_revert_errors(db, &reverts).await;
+
+    let db_revert_reasons: Vec<DatabaseRevertReason> = reverts
+        .into_iter()
+        .map(|(transaction, data, message)| {
+            let selector = match data.get(..4) {
+                Some(selector) => format!("0x{}", hex::encode(selector)),
+                None => String::new(),
+            };
+
+            let candidates: Vec<AbiError> = errors
+                .get(&(transaction.to_address.clone(), selector.clone()))
+                .into_iter()
+                .chain(errors.get(&(String::new(), selector)))
+                .flatten()
+                .cloned()
+                .collect();
+
+            DatabaseRevertReason::from_data(transaction, &data, message, &candidates)
+        })
+        .collect();
+
+    if !db_revert_reasons.is_empty() {
+        db.store_revert_reasons(&db_revert_reasons).await.unwrap();
+    }
+
+    info!(
+        "Stored {} revert reasons of failed transactions.",
+        db_revert_reasons.len()
+    );
+}
+
+/// Returns the custom errors grouped by contract and selector. Errors of the contract ABI are
+/// preferred since they include the arguments names, errors from the signatures registry are
+/// grouped under an empty contract and used as fallback.
+async fn get_revert_errors(
+    db: &Database,
+    reverts: &[(&DatabaseTransaction, Vec<u8>, Option<String>)],
+) -> HashMap<(String, String), Vec<AbiError>> {
+    let mut errors: HashMap<(String, String), Vec<AbiError>> = HashMap::new();
+
+    let contracts: Vec<(String, String)> = reverts
+        .iter()
+        .map(|(transaction, _, _)| (transaction.to_address.clone(), transaction.chain.clone()))
+        .collect::<HashSet<(String, String)>>()
+        .into_iter()
+        .collect();
+
+    let contracts_information = db
+        .get_contracts_information(&contracts)
+        .await
+        .unwrap_or_default();
+
+    for contract_information in contracts_information {
+        let contract: Contract = match &contract_information.abi {
+            Some(abi) => match serde_json::from_str(abi) {
+                Ok(contract) => contract,
+                Err(_) => continue,
+            },
+            None => continue,
+        };
+
+        for error in contract.errors() {
+            errors
+                .entry((
+                    contract_information.contract.clone(),
+                    format!("0x{}", hex::encode(error.selector())),
+                ))
+                .or_default()
+                .push(error.clone());
+        }
+    }
+
+    let selectors: Vec<String> = reverts
+        .iter()
+        .filter_map(|(_, data, _)| data.get(..4))
+        .map(|selector| format!("0x{}", hex::encode(selector)))
+        .collect::<HashSet<String>>()
+        .into_iter()
+        .collect();
+
+    for db_error in db.get_errors(&selectors).await.unwrap_or_default() {
+        if let Some(error) = db_error.to_abi() {
+            errors
+                .entry((String::new(), db_error.selector))
+                .or_default()
+                .push(error);
+        }
+    }
+
+    errors
+}
@@ -1,16 +1,23 @@
-use std::{thread::sleep, time::Duration};
+use std::time::{Duration, Instant};
 
 use dotenv::dotenv;
 use evm_indexer::{
+    adapters::import_adapters,
     chains::chains::ETHEREUM,
     configs::parser_config::EVMParserConfig,
     db::db::Database,
     parsers::{
-        erc20_balances::ERC20Balances, erc20_tokens::ERC20Tokens, erc20_transfers::ERC20Transfers,
+        contracts_code::ContractsCode, decoded_logs::DecodedLogs,
+        decoded_transactions::DecodedTransactions, dex_trades::DexTrades, ens_names::ENSNames,
+        erc20_approvals::ERC20Approvals, erc20_balances::ERC20Balances, erc20_supply::ERC20Supply,
+        erc20_tokens::ERC20Tokens, erc20_transfers::ERC20Transfers, inferred_abis::InferredAbis,
+        proxy_upgrades::ProxyUpgrades, safes::Safes, token_flags::TokenFlags,
+        token_prices::TokenPrices,
     },
 };
 use log::*;
 use simple_logger::SimpleLogger;
+use tokio::time::sleep;
 
 #[tokio::main()]
 async fn main() {
@@ -32,23 +39,41 @@ async fn main() {
         .await
         .expect("Unable to start DB connection.");
 
+    if let Some(path) = &config.adapters {
+        import_adapters(&db, path)
+            .await
+            .expect("Unable to import the adapters contracts.");
+    }
+
     if config.erc20_tokens {
         info!("Starting the ERC20 Tokens parser.");
 
         tokio::spawn({
             let db = db.clone();
+            let tokens_lists = ERC20Tokens::load_tokens_lists(&config.tokens_lists);
+            let refresh = Duration::from_secs(config.tokens_lists_refresh);
             async move {
                 let parser = ERC20Tokens {};
-                parser.parse_extenal(&db).await.unwrap();
+                parser.parse_extenal(&db, &tokens_lists).await.unwrap();
+
+                let mut last_refresh = Instant::now();
 
                 loop {
+                    if last_refresh.elapsed() > refresh {
+                        parser.parse_extenal(&db, &tokens_lists).await.unwrap();
+
+                        last_refresh = Instant::now();
+                    }
+
                     let data = parser.fetch(&db).await.unwrap();
 
                     info!("ERC20Tokens: Fetched {} transfers to parse.", data.len());
 
                     parser.parse(&db, &data).await.unwrap();
 
-                    sleep(Duration::from_secs(2))
+                    parser.retry(&db).await.unwrap();
+
+                    sleep(Duration::from_secs(2)).await
                 }
             }
         });
@@ -69,7 +94,269 @@ async fn main() {
 
                     parser.parse(&db, &data).await.unwrap();
 
-                    sleep(Duration::from_secs(2))
+                    sleep(Duration::from_secs(2)).await
+                }
+            }
+        });
+    }
+
+    if config.erc20_approvals {
+        info!("Starting the ERC20 Approvals parser.");
+
+        tokio::spawn({
+            let db = db.clone();
+            async move {
+                loop {
+                    let parser = ERC20Approvals {};
+
+                    let data = parser.fetch(&db).await.unwrap();
+
+                    info!("ERC20Approvals: Fetched {} logs to parse.", data.len());
+
+                    parser.parse(&db, &data).await.unwrap();
+
+                    sleep(Duration::from_secs(2)).await
+                }
+            }
+        });
+    }
+
+    if config.erc20_supply {
+        info!("Starting the ERC20 Supply parser.");
+
+        tokio::spawn({
+            let db = db.clone();
+            async move {
+                loop {
+                    let parser = ERC20Supply {};
+
+                    let data = parser.fetch(&db).await.unwrap();
+
+                    info!("ERC20Supply: Fetched {} transfers to parse.", data.len());
+
+                    parser.parse(&db, &data).await.unwrap();
+
+                    parser.reconcile(&db).await.unwrap();
+
+                    sleep(Duration::from_secs(2)).await
+                }
+            }
+        });
+    }
+
+    if config.decoded_logs {
+        info!("Starting the Decoded Logs parser.");
+
+        tokio::spawn({
+            let db = db.clone();
+            async move {
+                loop {
+                    let parser = DecodedLogs {};
+
+                    let data = parser.fetch(&db).await.unwrap();
+
+                    info!("DecodedLogs: Fetched {} logs to parse.", data.len());
+
+                    parser.parse(&db, &data).await.unwrap();
+
+                    sleep(Duration::from_secs(2)).await
+                }
+            }
+        });
+    }
+
+    if config.decoded_transactions {
+        info!("Starting the Decoded Transactions parser.");
+
+        tokio::spawn({
+            let db = db.clone();
+            async move {
+                loop {
+                    let parser = DecodedTransactions {};
+
+                    let data = parser.fetch(&db).await.unwrap();
+
+                    info!(
+                        "DecodedTransactions: Fetched {} transactions to parse.",
+                        data.len()
+                    );
+
+                    parser.parse(&db, &data).await.unwrap();
+
+                    sleep(Duration::from_secs(2)).await
+                }
+            }
+        });
+    }
+
+    if config.dex_trades {
+        info!("Starting the DEX Trades parser.");
+
+        tokio::spawn({
+            let db = db.clone();
+            async move {
+                loop {
+                    let parser = DexTrades {};
+
+                    let data = parser.fetch(&db).await.unwrap();
+
+                    info!("DexTrades: Fetched {} logs to parse.", data.len());
+
+                    parser.parse(&db, &data).await.unwrap();
+
+                    sleep(Duration::from_secs(2)).await
+                }
+            }
+        });
+    }
+
+    if config.token_prices {
+        info!("Starting the Token Prices parser.");
+
+        tokio::spawn({
+            let db = db.clone();
+            let feeds = TokenPrices::load_feeds(&config.price_feeds);
+            let anchors = TokenPrices::load_anchors(&config.price_anchors);
+            async move {
+                let parser = TokenPrices { feeds, anchors };
+
+                loop {
+                    let data = parser.fetch(&db).await.unwrap();
+
+                    info!("TokenPrices: Fetched {} logs to parse.", data.len());
+
+                    parser.parse(&db, &data).await.unwrap();
+
+                    sleep(Duration::from_secs(2)).await
+                }
+            }
+        });
+    }
+
+    if config.token_flags {
+        info!("Starting the Token Flags parser.");
+
+        tokio::spawn({
+            let db = db.clone();
+            async move {
+                loop {
+                    let parser = TokenFlags {};
+
+                    let data = parser.fetch(&db).await.unwrap();
+
+                    info!("TokenFlags: Fetched {} tokens to classify.", data.len());
+
+                    // Tokens are left unclassified to be fetched again when a signal failed.
+                    if let Err(err) = parser.parse(&db, &data).await {
+                        warn!("TokenFlags: Unable to classify tokens: {}", err);
+                    }
+
+                    sleep(Duration::from_secs(2)).await
+                }
+            }
+        });
+    }
+
+    if config.proxy_upgrades {
+        info!("Starting the Proxy Upgrades parser.");
+
+        tokio::spawn({
+            let db = db.clone();
+            async move {
+                loop {
+                    let parser = ProxyUpgrades {};
+
+                    let data = parser.fetch(&db).await.unwrap();
+
+                    info!("ProxyUpgrades: Fetched {} logs to parse.", data.len());
+
+                    parser.parse(&db, &data).await.unwrap();
+
+                    sleep(Duration::from_secs(2)).await
+                }
+            }
+        });
+    }
+
+    if config.contracts_code {
+        info!("Starting the Contracts Code parser.");
+
+        tokio::spawn({
+            let db = db.clone();
+            async move {
+                loop {
+                    let parser = ContractsCode {};
+
+                    let data = parser.fetch(&db).await.unwrap();
+
+                    info!("ContractsCode: Fetched {} contracts to parse.", data.len());
+
+                    parser.parse(&db, &data).await.unwrap();
+
+                    sleep(Duration::from_secs(2)).await
+                }
+            }
+        });
+    }
+
+    if config.inferred_abis {
+        info!("Starting the Inferred ABIs parser.");
+
+        tokio::spawn({
+            let db = db.clone();
+            async move {
+                loop {
+                    let parser = InferredAbis {};
+
+                    let data = parser.fetch(&db).await.unwrap();
+
+                    info!("InferredAbis: Fetched {} contracts to parse.", data.len());
+
+                    parser.parse(&db, &data).await.unwrap();
+
+                    sleep(Duration::from_secs(2)).await
+                }
+            }
+        });
+    }
+
+    if config.ens_names {
+        info!("Starting the ENS Names parser.");
+
+        tokio::spawn({
+            let db = db.clone();
+            async move {
+                loop {
+                    let parser = ENSNames {};
+
+                    let data = parser.fetch(&db).await.unwrap();
+
+                    info!("ENSNames: Fetched {} logs to parse.", data.len());
+
+                    parser.parse(&db, &data).await.unwrap();
+
+                    sleep(Duration::from_secs(2)).await
+                }
+            }
+        });
+    }
+
+    if config.safes {
+        info!("Starting the Safes parser.");
+
+        tokio::spawn({
+            let db = db.clone();
+            async move {
+                loop {
+                    let parser = Safes {};
+
+                    let data = parser.fetch(&db).await.unwrap();
+
+                    info!("Safes: Fetched {} logs to parse.", data.len());
+
+                    parser.parse(&db, &data).await.unwrap();
+
+                    sleep(Duration::from_secs(2)).await
                 }
             }
         });
@@ -86,6 +373,6 @@ async fn main() {
 
         erc20_transfers_parser.parse(&db, &logs).await.unwrap();
 
-        sleep(Duration::from_secs(2))
+        sleep(Duration::from_secs(2)).await
     }
 }
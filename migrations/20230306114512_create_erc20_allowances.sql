@@ -0,0 +1,21 @@
+ALTER TABLE logs ADD COLUMN erc20_approvals_parsed BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS logs_by_erc20_approvals_parsed ON logs (erc20_approvals_parsed) STORING (address, chain, data, removed, topics);
+
+CREATE TABLE erc20_allowances (
+  block BIGINT NOT NULL,
+  chain TEXT NOT NULL,
+  log_index BIGINT NOT NULL,
+  owner TEXT NOT NULL,
+  spender TEXT NOT NULL,
+  token TEXT NOT NULL,
+  unlimited BOOLEAN NOT NULL,
+  value TEXT NOT NULL,
+  PRIMARY KEY (owner, spender, token, chain)
+);
+
+CREATE INDEX IF NOT EXISTS erc20_allowances_by_owner ON erc20_allowances (owner, chain) STORING (spender, token, unlimited, value);
+
+CREATE INDEX IF NOT EXISTS erc20_allowances_by_spender ON erc20_allowances (spender, chain) STORING (owner, token, unlimited, value);
+
+CREATE INDEX IF NOT EXISTS erc20_allowances_by_unlimited ON erc20_allowances (unlimited, block DESC) STORING (owner, spender, token);
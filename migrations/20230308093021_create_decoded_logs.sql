@@ -0,0 +1,23 @@
+ALTER TABLE logs ADD COLUMN decoded_logs_parsed BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS logs_by_decoded_logs_parsed ON logs (decoded_logs_parsed) STORING (address, chain, data, removed, topics);
+
+CREATE TABLE decoded_logs (
+  address TEXT NOT NULL,
+  block_number BIGINT NOT NULL,
+  chain TEXT NOT NULL,
+  event TEXT NOT NULL,
+  hash TEXT NOT NULL,
+  log_index BIGINT NOT NULL,
+  params JSONB NOT NULL,
+  signature TEXT NOT NULL,
+  source TEXT NOT NULL,
+  topic TEXT NOT NULL,
+  PRIMARY KEY (hash, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS decoded_logs_by_address ON decoded_logs (address, chain, block_number DESC);
+
+CREATE INDEX IF NOT EXISTS decoded_logs_by_signature ON decoded_logs (signature, chain, block_number DESC);
+
+CREATE INVERTED INDEX IF NOT EXISTS decoded_logs_by_params ON decoded_logs (params);
@@ -0,0 +1,24 @@
+ALTER TABLE methods ADD COLUMN signature TEXT;
+
+ALTER TABLE transactions ADD COLUMN decoded_transactions_parsed BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS transactions_by_decoded_transactions_parsed ON transactions (decoded_transactions_parsed);
+
+CREATE TABLE decoded_transactions (
+  block_number BIGINT NOT NULL,
+  chain TEXT NOT NULL,
+  hash TEXT NOT NULL,
+  method TEXT NOT NULL,
+  name TEXT NOT NULL,
+  params JSONB NOT NULL,
+  signature TEXT NOT NULL,
+  source TEXT NOT NULL,
+  to_address TEXT NOT NULL,
+  CONSTRAINT decoded_transactions_pkey PRIMARY KEY (hash)
+);
+
+CREATE INDEX IF NOT EXISTS decoded_transactions_by_to_address ON decoded_transactions (to_address, chain, block_number DESC);
+
+CREATE INDEX IF NOT EXISTS decoded_transactions_by_name ON decoded_transactions (name, chain, block_number DESC);
+
+CREATE INVERTED INDEX IF NOT EXISTS decoded_transactions_by_params ON decoded_transactions (params);
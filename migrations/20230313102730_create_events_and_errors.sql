@@ -0,0 +1,19 @@
+CREATE TABLE events (
+  abi TEXT NOT NULL,
+  indexed BOOLEAN[] NOT NULL,
+  name TEXT NOT NULL,
+  signature TEXT NOT NULL,
+  topic TEXT NOT NULL,
+  CONSTRAINT events_pkey PRIMARY KEY (topic, indexed)
+);
+
+CREATE INDEX IF NOT EXISTS events_by_name ON events (name);
+
+CREATE TABLE errors (
+  name TEXT NOT NULL,
+  selector TEXT NOT NULL,
+  signature TEXT NOT NULL,
+  CONSTRAINT errors_pkey PRIMARY KEY (selector, signature)
+);
+
+CREATE INDEX IF NOT EXISTS errors_by_name ON errors (name);
@@ -0,0 +1,13 @@
+CREATE TABLE revert_reasons (
+  chain TEXT NOT NULL,
+  data TEXT NOT NULL,
+  hash TEXT NOT NULL,
+  kind TEXT NOT NULL,
+  reason TEXT NOT NULL,
+  signature TEXT,
+  CONSTRAINT revert_reasons_pkey PRIMARY KEY (hash)
+);
+
+CREATE INDEX IF NOT EXISTS revert_reasons_by_kind ON revert_reasons (kind, chain);
+
+CREATE INDEX IF NOT EXISTS revert_reasons_by_signature ON revert_reasons (signature, chain);
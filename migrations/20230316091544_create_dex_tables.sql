@@ -0,0 +1,77 @@
+ALTER TABLE logs ADD COLUMN dex_trades_parsed BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS logs_by_dex_trades_parsed ON logs (dex_trades_parsed);
+
+CREATE TABLE dex_pools (
+  address TEXT NOT NULL,
+  chain TEXT NOT NULL,
+  factory TEXT,
+  fee BIGINT,
+  protocol TEXT NOT NULL,
+  token0 TEXT NOT NULL,
+  token1 TEXT NOT NULL,
+  CONSTRAINT dex_pools_pkey PRIMARY KEY (address, chain)
+);
+
+CREATE INDEX IF NOT EXISTS dex_pools_by_token0 ON dex_pools (token0, chain);
+
+CREATE INDEX IF NOT EXISTS dex_pools_by_token1 ON dex_pools (token1, chain);
+
+CREATE TABLE dex_reserves (
+  block_number BIGINT NOT NULL,
+  chain TEXT NOT NULL,
+  log_index BIGINT NOT NULL,
+  pool TEXT NOT NULL,
+  reserve0 TEXT NOT NULL,
+  reserve1 TEXT NOT NULL,
+  CONSTRAINT dex_reserves_pkey PRIMARY KEY (pool, chain)
+);
+
+CREATE TABLE dex_trades (
+  amount_in TEXT NOT NULL,
+  amount_out TEXT NOT NULL,
+  block_number BIGINT NOT NULL,
+  chain TEXT NOT NULL,
+  hash TEXT NOT NULL,
+  log_index BIGINT NOT NULL,
+  pool TEXT NOT NULL,
+  protocol TEXT NOT NULL,
+  recipient TEXT NOT NULL,
+  sender TEXT NOT NULL,
+  token_in TEXT NOT NULL,
+  token_out TEXT NOT NULL,
+  trader TEXT NOT NULL,
+  CONSTRAINT dex_trades_pkey PRIMARY KEY (hash, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS dex_trades_by_pool ON dex_trades (pool, chain, block_number DESC);
+
+CREATE INDEX IF NOT EXISTS dex_trades_by_trader ON dex_trades (trader, chain, block_number DESC);
+
+CREATE INDEX IF NOT EXISTS dex_trades_by_token_in ON dex_trades (token_in, chain, block_number DESC);
+
+CREATE INDEX IF NOT EXISTS dex_trades_by_token_out ON dex_trades (token_out, chain, block_number DESC);
+
+CREATE TABLE dex_liquidity_events (
+  amount0 TEXT NOT NULL,
+  amount1 TEXT NOT NULL,
+  block_number BIGINT NOT NULL,
+  chain TEXT NOT NULL,
+  event TEXT NOT NULL,
+  hash TEXT NOT NULL,
+  liquidity TEXT,
+  log_index BIGINT NOT NULL,
+  owner TEXT NOT NULL,
+  pool TEXT NOT NULL,
+  protocol TEXT NOT NULL,
+  provider TEXT NOT NULL,
+  tick_lower BIGINT,
+  tick_upper BIGINT,
+  token0 TEXT NOT NULL,
+  token1 TEXT NOT NULL,
+  CONSTRAINT dex_liquidity_events_pkey PRIMARY KEY (hash, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS dex_liquidity_events_by_pool ON dex_liquidity_events (pool, chain, block_number DESC);
+
+CREATE INDEX IF NOT EXISTS dex_liquidity_events_by_provider ON dex_liquidity_events (provider, chain, block_number DESC);
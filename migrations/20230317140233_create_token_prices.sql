@@ -0,0 +1,17 @@
+ALTER TABLE logs ADD COLUMN token_prices_parsed BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS logs_by_token_prices_parsed ON logs (token_prices_parsed);
+
+CREATE TABLE token_prices (
+  block_number BIGINT NOT NULL,
+  chain TEXT NOT NULL,
+  hour BIGINT NOT NULL,
+  log_index BIGINT NOT NULL,
+  price DOUBLE PRECISION NOT NULL,
+  source TEXT NOT NULL,
+  source_address TEXT NOT NULL,
+  token TEXT NOT NULL,
+  CONSTRAINT token_prices_pkey PRIMARY KEY (token, chain, hour, source)
+);
+
+CREATE INDEX IF NOT EXISTS token_prices_by_hour ON token_prices (chain, hour DESC);
@@ -0,0 +1,27 @@
+ALTER TABLE erc20_transfers ADD COLUMN erc20_supply_parsed BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS erc20_transfers_by_erc20_supply_parsed ON erc20_transfers (erc20_supply_parsed) STORING (chain, from_address, to_address, token, value);
+
+CREATE TABLE erc20_supply (
+  burned DECIMAL NOT NULL DEFAULT 0,
+  chain TEXT NOT NULL,
+  last_block BIGINT NOT NULL,
+  minted DECIMAL NOT NULL DEFAULT 0,
+  onchain_block BIGINT,
+  onchain_supply DECIMAL,
+  reconciled_at TIMESTAMP,
+  supply DECIMAL AS (minted - burned) STORED,
+  token TEXT NOT NULL,
+  CONSTRAINT erc20_supply_pkey PRIMARY KEY (token, chain)
+);
+
+CREATE INDEX IF NOT EXISTS erc20_supply_by_reconciled_at ON erc20_supply (reconciled_at);
+
+CREATE TABLE erc20_supply_changes (
+  block_number BIGINT NOT NULL,
+  burned DECIMAL NOT NULL,
+  chain TEXT NOT NULL,
+  minted DECIMAL NOT NULL,
+  token TEXT NOT NULL,
+  CONSTRAINT erc20_supply_changes_pkey PRIMARY KEY (token, chain, block_number)
+);
@@ -0,0 +1,41 @@
+CREATE INDEX IF NOT EXISTS erc20_balances_by_token_balance ON erc20_balances (token, chain, balance DESC);
+
+CREATE TABLE erc20_holders (
+  chain TEXT NOT NULL,
+  holders BIGINT NOT NULL,
+  token TEXT NOT NULL,
+  CONSTRAINT erc20_holders_pkey PRIMARY KEY (token, chain)
+);
+
+CREATE TABLE erc20_top_holders (
+  address TEXT NOT NULL,
+  balance DOUBLE PRECISION NOT NULL,
+  chain TEXT NOT NULL,
+  token TEXT NOT NULL,
+  CONSTRAINT erc20_top_holders_pkey PRIMARY KEY (token, chain, address)
+);
+
+CREATE INDEX IF NOT EXISTS erc20_top_holders_by_balance ON erc20_top_holders (token, chain, balance DESC);
+
+INSERT INTO erc20_holders (token, chain, holders)
+SELECT token, chain, count(*) FROM erc20_balances
+WHERE balance > 0 AND address NOT IN (
+  '0x0000000000000000000000000000000000000000',
+  '0x0000000000000000000000000000000000000001',
+  '0x000000000000000000000000000000000000dead',
+  '0xdead000000000000000042069420694206942069'
+)
+GROUP BY token, chain;
+
+INSERT INTO erc20_top_holders (address, balance, chain, token)
+SELECT address, balance, chain, token FROM (
+  SELECT address, balance, chain, token, row_number() OVER (PARTITION BY token, chain ORDER BY balance DESC) AS position
+  FROM erc20_balances
+  WHERE balance > 0 AND address NOT IN (
+    '0x0000000000000000000000000000000000000000',
+    '0x0000000000000000000000000000000000000001',
+    '0x000000000000000000000000000000000000dead',
+    '0xdead000000000000000042069420694206942069'
+  )
+)
+WHERE position <= 100;
@@ -0,0 +1,10 @@
+ALTER TABLE erc20_tokens ADD COLUMN status TEXT NOT NULL DEFAULT 'ok';
+
+ALTER TABLE erc20_tokens ADD COLUMN attempts BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE erc20_tokens ADD COLUMN next_attempt_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS erc20_tokens_by_status ON erc20_tokens (status, next_attempt_at);
+
+-- Tokens stored with a fallback of 0 decimals when the RPC failed are fetched again.
+UPDATE erc20_tokens SET status = 'retry-later', decimals = NULL, next_attempt_at = now() WHERE decimals = 0;
@@ -0,0 +1,9 @@
+ALTER TABLE erc20_tokens ADD COLUMN logo_uri TEXT;
+
+ALTER TABLE erc20_tokens ADD COLUMN source TEXT;
+
+ALTER TABLE erc20_tokens ADD COLUMN tags TEXT[];
+
+ALTER TABLE erc20_tokens ADD COLUMN trust TEXT NOT NULL DEFAULT 'unverified';
+
+CREATE INDEX IF NOT EXISTS erc20_tokens_by_trust ON erc20_tokens (chain, trust);
@@ -0,0 +1,13 @@
+CREATE TABLE token_flags (
+  chain TEXT NOT NULL,
+  reasons TEXT[] NOT NULL,
+  score BIGINT NOT NULL,
+  spam BOOLEAN NOT NULL,
+  token TEXT NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  CONSTRAINT token_flags_pkey PRIMARY KEY (token, chain)
+);
+
+CREATE INDEX IF NOT EXISTS token_flags_by_spam ON token_flags (chain, spam);
+
+CREATE INDEX IF NOT EXISTS token_flags_by_updated_at ON token_flags (updated_at);
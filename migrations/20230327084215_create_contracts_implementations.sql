@@ -0,0 +1,16 @@
+ALTER TABLE logs ADD COLUMN proxy_upgrades_parsed BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS logs_by_proxy_upgrades_parsed ON logs (proxy_upgrades_parsed) STORING (address, chain, data, removed, topics);
+
+ALTER TABLE contracts_information ADD COLUMN implementation TEXT;
+
+CREATE TABLE contracts_implementations (
+  block BIGINT NOT NULL,
+  chain TEXT NOT NULL,
+  contract TEXT NOT NULL,
+  implementation TEXT NOT NULL,
+  standard TEXT NOT NULL,
+  CONSTRAINT contracts_implementations_pkey PRIMARY KEY (contract, chain, block)
+);
+
+CREATE INDEX IF NOT EXISTS contracts_implementations_by_implementation ON contracts_implementations (implementation, chain);
@@ -0,0 +1,5 @@
+ALTER TABLE contracts ADD COLUMN attempts BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE contracts ADD COLUMN next_attempt_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS contracts_by_next_attempt_at ON contracts (chain, next_attempt_at) STORING (parsed, verified);
@@ -0,0 +1,5 @@
+ALTER TABLE contracts ADD COLUMN interactions BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE contracts ADD COLUMN interactions_updated_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS contracts_by_interactions ON contracts (chain, interactions DESC) STORING (next_attempt_at, parsed, verified);
@@ -0,0 +1,14 @@
+CREATE TABLE contracts_source (
+  chain TEXT NOT NULL,
+  compiler_version TEXT,
+  constructor_arguments TEXT,
+  contract TEXT NOT NULL,
+  decoded_constructor_arguments JSONB,
+  evm_version TEXT,
+  files JSONB NOT NULL,
+  license_type TEXT,
+  optimization_used BOOLEAN,
+  runs BIGINT,
+  source TEXT NOT NULL,
+  CONSTRAINT contracts_source_pkey PRIMARY KEY (contract, chain)
+);
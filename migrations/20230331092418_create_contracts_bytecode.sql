@@ -0,0 +1,18 @@
+ALTER TABLE contracts ADD COLUMN code_hash TEXT;
+
+ALTER TABLE contracts ADD COLUMN code_parsed BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE contracts ADD COLUMN standards TEXT[];
+
+CREATE INDEX IF NOT EXISTS contracts_by_code_parsed ON contracts (code_parsed);
+
+CREATE INDEX IF NOT EXISTS contracts_by_code_hash ON contracts (code_hash) STORING (standards);
+
+CREATE INDEX IF NOT EXISTS contracts_by_standards ON contracts (contract, chain) STORING (code_parsed, standards);
+
+CREATE TABLE contracts_bytecode (
+  bytecode TEXT NOT NULL,
+  code_hash TEXT NOT NULL,
+  size BIGINT NOT NULL,
+  CONSTRAINT contracts_bytecode_pkey PRIMARY KEY (code_hash)
+);
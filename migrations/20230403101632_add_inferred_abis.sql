@@ -0,0 +1,5 @@
+ALTER TABLE contracts ADD COLUMN abi_inferred BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS contracts_by_abi_inferred ON contracts (abi_inferred) STORING (code_hash, parsed, verified);
+
+ALTER TABLE contracts_information ADD COLUMN inferred BOOLEAN NOT NULL DEFAULT false;
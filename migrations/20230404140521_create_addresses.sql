@@ -0,0 +1,13 @@
+CREATE TABLE addresses (
+  address TEXT NOT NULL,
+  chain TEXT NOT NULL,
+  contract BOOLEAN NOT NULL DEFAULT false,
+  first_seen_block BIGINT NOT NULL,
+  last_seen_block BIGINT NOT NULL,
+  nonce BIGINT NOT NULL DEFAULT 0,
+  tx_in BIGINT NOT NULL DEFAULT 0,
+  tx_out BIGINT NOT NULL DEFAULT 0,
+  CONSTRAINT addresses_pkey PRIMARY KEY (address, chain)
+);
+
+CREATE INDEX IF NOT EXISTS addresses_by_chain_last_seen ON addresses (chain, last_seen_block DESC) STORING (contract, tx_in, tx_out);
@@ -0,0 +1,14 @@
+CREATE TABLE address_activity (
+  address TEXT NOT NULL,
+  amount TEXT,
+  block_number BIGINT NOT NULL,
+  chain TEXT NOT NULL,
+  counterparty TEXT,
+  hash TEXT NOT NULL,
+  kind TEXT NOT NULL,
+  log_index BIGINT NOT NULL,
+  token TEXT,
+  CONSTRAINT address_activity_pkey PRIMARY KEY (address, chain, block_number DESC, hash DESC, log_index DESC, kind DESC)
+);
+
+CREATE INDEX IF NOT EXISTS address_activity_by_hash ON address_activity (hash);
@@ -0,0 +1,50 @@
+ALTER TABLE logs ADD COLUMN ens_names_parsed BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS logs_by_ens_names_parsed ON logs (chain, ens_names_parsed) STORING (address, data, removed, topics);
+
+CREATE TABLE ens_labels (
+  label TEXT NOT NULL,
+  labelhash TEXT NOT NULL,
+  CONSTRAINT ens_labels_pkey PRIMARY KEY (labelhash)
+);
+
+CREATE TABLE ens_names (
+  labelhash TEXT NOT NULL,
+  name TEXT,
+  node TEXT NOT NULL,
+  parent TEXT NOT NULL,
+  CONSTRAINT ens_names_pkey PRIMARY KEY (node)
+);
+
+CREATE INDEX IF NOT EXISTS ens_names_by_name ON ens_names (name);
+
+CREATE INDEX IF NOT EXISTS ens_names_by_labelhash ON ens_names (labelhash) STORING (name, parent);
+
+CREATE INDEX IF NOT EXISTS ens_names_by_parent ON ens_names (parent) STORING (labelhash, name);
+
+CREATE TABLE ens_records (
+  block_number BIGINT NOT NULL,
+  contract TEXT NOT NULL,
+  hash TEXT NOT NULL,
+  kind TEXT NOT NULL,
+  log_index BIGINT NOT NULL,
+  node TEXT NOT NULL,
+  value TEXT NOT NULL,
+  CONSTRAINT ens_records_pkey PRIMARY KEY (node, kind, block_number DESC, log_index DESC)
+);
+
+CREATE INDEX IF NOT EXISTS ens_records_by_value ON ens_records (kind, value) STORING (contract, node);
+
+-- The root and the top level nodes are created long before most indexed blocks.
+INSERT INTO ens_labels (label, labelhash)
+VALUES
+  ('eth', '0x4f5b812789fc606be1b3b16908db13fc7a9adf7ca72641f84d75b47069d3d7f0'),
+  ('reverse', '0xdec08c9dbbdd0890e300eb5062089b2d4b1c40e3673bbccb5423f7b37dcf9a9c'),
+  ('addr', '0xe5e14487b78f85faa6e1808e89246cf57dd34831548ff2e6097380d98db2504a');
+
+INSERT INTO ens_names (labelhash, name, node, parent)
+VALUES
+  ('0x0000000000000000000000000000000000000000000000000000000000000000', '', '0x0000000000000000000000000000000000000000000000000000000000000000', '0x0000000000000000000000000000000000000000000000000000000000000000'),
+  ('0x4f5b812789fc606be1b3b16908db13fc7a9adf7ca72641f84d75b47069d3d7f0', 'eth', '0x93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae', '0x0000000000000000000000000000000000000000000000000000000000000000'),
+  ('0xdec08c9dbbdd0890e300eb5062089b2d4b1c40e3673bbccb5423f7b37dcf9a9c', 'reverse', '0xa097f6721ce401e757d1223a763fef49b8b5f90bb18567ddb86fd205dff71d34', '0x0000000000000000000000000000000000000000000000000000000000000000'),
+  ('0xe5e14487b78f85faa6e1808e89246cf57dd34831548ff2e6097380d98db2504a', 'addr.reverse', '0x91d1777781884d03a6757a803996e38de2a42967fb37eeaca72729271025a9e2', '0xa097f6721ce401e757d1223a763fef49b8b5f90bb18567ddb86fd205dff71d34');
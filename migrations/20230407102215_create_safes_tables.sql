@@ -0,0 +1,54 @@
+ALTER TABLE logs ADD COLUMN safes_parsed BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS logs_by_safes_parsed ON logs (safes_parsed) STORING (address, chain, data, removed, topics);
+
+CREATE TABLE safes (
+  address TEXT NOT NULL,
+  chain TEXT NOT NULL,
+  created_block BIGINT,
+  factory TEXT,
+  singleton TEXT,
+  CONSTRAINT safes_pkey PRIMARY KEY (address, chain)
+);
+
+CREATE TABLE safes_owners (
+  added BOOLEAN NOT NULL,
+  block_number BIGINT NOT NULL,
+  chain TEXT NOT NULL,
+  hash TEXT NOT NULL,
+  log_index BIGINT NOT NULL,
+  owner TEXT NOT NULL,
+  safe TEXT NOT NULL,
+  CONSTRAINT safes_owners_pkey PRIMARY KEY (safe, chain, owner, block_number, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS safes_owners_by_owner ON safes_owners (owner, chain) STORING (added);
+
+CREATE TABLE safes_thresholds (
+  block_number BIGINT NOT NULL,
+  chain TEXT NOT NULL,
+  hash TEXT NOT NULL,
+  log_index BIGINT NOT NULL,
+  safe TEXT NOT NULL,
+  threshold BIGINT NOT NULL,
+  CONSTRAINT safes_thresholds_pkey PRIMARY KEY (safe, chain, block_number, log_index)
+);
+
+CREATE TABLE safes_executions (
+  block_number BIGINT NOT NULL,
+  chain TEXT NOT NULL,
+  data TEXT,
+  hash TEXT NOT NULL,
+  log_index BIGINT NOT NULL,
+  method TEXT,
+  operation BIGINT,
+  payment TEXT NOT NULL,
+  safe TEXT NOT NULL,
+  safe_tx_hash TEXT NOT NULL,
+  success BOOLEAN NOT NULL,
+  to_address TEXT,
+  value TEXT,
+  CONSTRAINT safes_executions_pkey PRIMARY KEY (hash, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS safes_executions_by_safe ON safes_executions (safe, chain, block_number DESC);
@@ -0,0 +1,21 @@
+-- Deposit and Withdrawal logs of the wrapped native tokens parsed before they were stored as
+-- transfers are parsed again.
+UPDATE logs SET erc20_transfers_parsed = false
+WHERE (chain, address) IN (
+  ('ethereum', '0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2'),
+  ('polygon', '0x0d500b1d8e8ef31e21c99d1db9a6444d3adf1270'),
+  ('fantom', '0x21be370d5312f44cb42ce377bc9b8a0cef1a4c83'),
+  ('bsc', '0xbb4cdb9cbd36b01bd1cbaebf2de08d9173bc095c'),
+  ('gnosis', '0xe91d153e0b41518a2ce8dd3d7944fa863463a97d'),
+  ('optimism', '0x4200000000000000000000000000000000000006'),
+  ('arbitrum', '0x82af49447d8a07e3bd95bd0d56f35241523fbab1'),
+  ('arbitrum-nova', '0x722e8bdd2ce80a4422e880164f2079488e115365'),
+  ('moonbeam', '0xacc15dc74880c9944775448304b263d191c6077f'),
+  ('avalanche', '0xb31f66aa3c1e785363f0875a1b74e27b85fd66c7'),
+  ('bittorrent', '0x23181f21dea5936e24163ffaba4ea3b316b57f3c')
+)
+AND array_length(topics, 1) = 2
+AND topics[1] IN (
+  '0xe1fffcc4923d04b559f4d29a8bfc6cda04eb5b0d3c460751c2402c5c5cc9109c',
+  '0x7fcf532c15f0a6db0bd6d0e038bea71d30d808c7d98cb3bf7268a95bf5081b65'
+);
@@ -0,0 +1,6 @@
+CREATE TABLE dex_pools_unresolved (
+  address TEXT NOT NULL,
+  chain TEXT NOT NULL,
+  next_attempt_at TIMESTAMP NOT NULL,
+  CONSTRAINT dex_pools_unresolved_pkey PRIMARY KEY (address, chain)
+);
@@ -0,0 +1 @@
+ALTER TABLE token_prices ADD COLUMN liquidity DOUBLE PRECISION;
@@ -0,0 +1,10 @@
+ALTER TABLE contracts ADD COLUMN code_attempts BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE contracts ADD COLUMN code_next_attempt_at TIMESTAMP;
+
+DROP INDEX IF EXISTS contracts_by_code_parsed;
+
+CREATE INDEX IF NOT EXISTS contracts_by_code_parsed ON contracts (code_parsed, code_next_attempt_at);
+
+-- Contracts stored without code or standards by earlier versions of the parser are fetched again.
+UPDATE contracts SET code_parsed = false WHERE code_parsed = true AND code_hash IS NULL;
@@ -0,0 +1,185 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::chains::chains::Chain;
+
+use super::{
+    rate_limiter::RateLimiter, AbiSource, ContractSource, SourceCode, ABI_SOURCE_ETHERSCAN,
+};
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Response {
+    pub status: String,
+    pub message: String,
+    pub result: Vec<ContractDataResult>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractDataResult {
+    #[serde(rename = "SourceCode")]
+    pub source_code: String,
+    #[serde(rename = "ABI")]
+    pub abi: String,
+    #[serde(rename = "ContractName")]
+    pub contract_name: String,
+    #[serde(rename = "CompilerVersion")]
+    pub compiler_version: String,
+    #[serde(rename = "OptimizationUsed")]
+    pub optimization_used: String,
+    #[serde(rename = "Runs")]
+    pub runs: String,
+    #[serde(rename = "ConstructorArguments")]
+    pub constructor_arguments: String,
+    #[serde(rename = "EVMVersion")]
+    pub evmversion: String,
+    #[serde(rename = "Library")]
+    pub library: String,
+    #[serde(rename = "LicenseType")]
+    pub license_type: String,
+    #[serde(rename = "Proxy")]
+    pub proxy: String,
+    #[serde(rename = "Implementation")]
+    pub implementation: String,
+    #[serde(rename = "SwarmSource")]
+    pub swarm_source: String,
+}
+
+/// Block explorers compatible with the Etherscan `getsourcecode` API. The rate limiter is
+/// shared by every worker using the same explorer key.
+pub struct Etherscan {
+    pub client: Client,
+    pub limiter: Arc<RateLimiter>,
+    pub token: String,
+}
+
+#[async_trait]
+impl AbiSource for Etherscan {
+    fn name(&self) -> &'static str {
+        ABI_SOURCE_ETHERSCAN
+    }
+
+    async fn get_contract(&self, chain: &Chain, address: &str) -> Result<Option<ContractSource>> {
+        let uri_str = if chain.abi_source_require_auth {
+            format!(
+                "{}api?module=contract&action=getsourcecode&address={}&apikey={}",
+                chain.abi_source_api, address, self.token
+            )
+        } else {
+            format!(
+                "{}api?module=contract&action=getsourcecode&address={}",
+                chain.abi_source_api, address
+            )
+        };
+
+        self.limiter.acquire().await;
+
+        let response = self.client.get(uri_str).send().await?.text().await?;
+
+        let contract_response: Response = serde_json::from_str(&response)?;
+
+        if contract_response.status != "1" || contract_response.message != "OK" {
+            return Err(anyhow::anyhow!(
+                "Explorer returned {}",
+                contract_response.message
+            ));
+        }
+
+        let result = match contract_response.result.into_iter().next() {
+            Some(result) => result,
+            None => return Err(anyhow::anyhow!("Explorer returned an empty result")),
+        };
+
+        if result.abi == "Contract source code not verified" {
+            return Ok(None);
+        }
+
+        let source_code = result.to_source_code();
+
+        Ok(Some(ContractSource {
+            abi: result.abi,
+            name: Some(result.contract_name),
+            source_code: Some(source_code),
+            implementation: if result.proxy == "1" && !result.implementation.is_empty() {
+                Some(result.implementation.to_lowercase())
+            } else {
+                None
+            },
+        }))
+    }
+}
+
+impl ContractDataResult {
+    /// Explorers return a single file as plain text, multiple files as a JSON object of files
+    /// and the standard JSON input wrapped in an extra pair of braces.
+    pub fn to_source_code(&self) -> SourceCode {
+        let mut files: BTreeMap<String, String> = BTreeMap::new();
+
+        let source = self.source_code.trim();
+
+        let json = if source.starts_with("{{") && source.ends_with("}}") {
+            serde_json::from_str::<Value>(&source[1..source.len() - 1]).ok()
+        } else if source.starts_with('{') {
+            serde_json::from_str::<Value>(source).ok()
+        } else {
+            None
+        };
+
+        match json {
+            Some(json) => {
+                let sources = match json.get("sources") {
+                    Some(sources) => sources.clone(),
+                    None => json,
+                };
+
+                if let Some(sources) = sources.as_object() {
+                    for (path, file) in sources {
+                        if let Some(content) = file["content"].as_str() {
+                            files.insert(path.clone(), content.to_owned());
+                        }
+                    }
+                }
+            }
+            None => {
+                let extension = if self.compiler_version.starts_with("vyper") {
+                    "vy"
+                } else {
+                    "sol"
+                };
+
+                files.insert(
+                    format!("{}.{}", self.contract_name, extension),
+                    self.source_code.clone(),
+                );
+            }
+        }
+
+        let optional = |value: &str| {
+            if value.is_empty() || value == "Default" || value == "None" {
+                None
+            } else {
+                Some(value.to_owned())
+            }
+        };
+
+        SourceCode {
+            compiler_version: optional(&self.compiler_version),
+            constructor_arguments: optional(&self.constructor_arguments),
+            evm_version: optional(&self.evmversion),
+            files,
+            license_type: optional(&self.license_type),
+            optimization_used: match self.optimization_used.as_str() {
+                "1" => Some(true),
+                "0" => Some(false),
+                _ => None,
+            },
+            runs: self.runs.parse::<i64>().ok(),
+        }
+    }
+}
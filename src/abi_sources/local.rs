@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::chains::chains::Chain;
+
+use super::{AbiSource, ContractSource, ABI_SOURCE_LOCAL};
+
+/// Directory with the ABIs stored as `<chain>/<address>.json`. Each file is a JSON ABI or an
+/// object with the `abi` and an optional `name` of the contract.
+pub struct LocalDirectory {
+    pub path: String,
+}
+
+#[async_trait]
+impl AbiSource for LocalDirectory {
+    fn name(&self) -> &'static str {
+        ABI_SOURCE_LOCAL
+    }
+
+    async fn get_contract(&self, chain: &Chain, address: &str) -> Result<Option<ContractSource>> {
+        let path = Path::new(&self.path)
+            .join(chain.name)
+            .join(format!("{}.json", address.to_lowercase()));
+
+        let file = match std::fs::read_to_string(path) {
+            Ok(file) => file,
+            Err(_) => return Ok(None),
+        };
+
+        let value: Value = serde_json::from_str(&file)?;
+
+        let (abi, name) = if value.is_array() {
+            (value.to_string(), None)
+        } else if value["abi"].is_array() {
+            (
+                value["abi"].to_string(),
+                value["name"].as_str().map(|name| name.to_owned()),
+            )
+        } else {
+            return Err(anyhow::anyhow!("Invalid ABI file for {}", address));
+        };
+
+        Ok(Some(ContractSource {
+            abi,
+            name,
+            implementation: None,
+            source_code: None,
+        }))
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::chains::chains::Chain;
+
+pub mod etherscan;
+pub mod local;
+pub mod rate_limiter;
+pub mod sourcify;
+
+pub const ABI_SOURCE_ETHERSCAN: &str = "etherscan";
+pub const ABI_SOURCE_SOURCIFY: &str = "sourcify";
+pub const ABI_SOURCE_LOCAL: &str = "local";
+
+/// ABI of a verified contract.
+#[derive(Debug, Clone, Default)]
+pub struct ContractSource {
+    pub abi: String,
+    pub name: Option<String>,
+    /// Implementation reported by the source when the contract is a proxy.
+    pub implementation: Option<String>,
+    pub source_code: Option<SourceCode>,
+}
+
+/// Verified source files and the compiler settings used to build the contract.
+#[derive(Debug, Clone, Default)]
+pub struct SourceCode {
+    pub compiler_version: Option<String>,
+    /// Hex encoded arguments appended to the creation code.
+    pub constructor_arguments: Option<String>,
+    pub evm_version: Option<String>,
+    /// Content of each source file by its path.
+    pub files: BTreeMap<String, String>,
+    pub license_type: Option<String>,
+    pub optimization_used: Option<bool>,
+    pub runs: Option<i64>,
+}
+
+#[async_trait]
+pub trait AbiSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Returns `None` when the contract is not verified in this source and an error when the
+    /// source couldn't be queried.
+    async fn get_contract(&self, chain: &Chain, address: &str) -> Result<Option<ContractSource>>;
+}
+
+/// Queries the sources in order and returns the first verified contract with the name of its
+/// source. When no source has the contract verified but any of them failed, the error is
+/// returned so the contract is fetched again later.
+pub async fn get_contract_source(
+    sources: &[Box<dyn AbiSource>],
+    chain: &Chain,
+    address: &str,
+) -> Result<Option<(ContractSource, &'static str)>> {
+    let mut error = None;
+
+    for source in sources {
+        match source.get_contract(chain, address).await {
+            Ok(Some(contract)) => return Ok(Some((contract, source.name()))),
+            Ok(None) => continue,
+            Err(err) => error = Some(err),
+        }
+    }
+
+    match error {
+        Some(err) => Err(err),
+        None => Ok(None),
+    }
+}
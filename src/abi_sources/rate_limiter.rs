@@ -0,0 +1,49 @@
+use std::time::{Duration, Instant};
+
+use tokio::{sync::Mutex, time::sleep};
+
+/// Token bucket shared by the workers using the same API. The bucket starts full, allowing a
+/// burst of `capacity` requests, and is refilled at `rate` requests per second.
+pub struct RateLimiter {
+    pub capacity: f64,
+    pub rate: f64,
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: f64) -> Self {
+        let capacity = rate.max(1.0);
+
+        Self {
+            capacity,
+            rate,
+            bucket: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// Waits until a token is available and consumes it.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+
+                let (tokens, last_refill) = *bucket;
+
+                let tokens =
+                    (tokens + last_refill.elapsed().as_secs_f64() * self.rate).min(self.capacity);
+
+                if tokens >= 1.0 {
+                    *bucket = (tokens - 1.0, Instant::now());
+
+                    return;
+                }
+
+                *bucket = (tokens, Instant::now());
+
+                Duration::from_secs_f64((1.0 - tokens) / self.rate)
+            };
+
+            sleep(wait).await;
+        }
+    }
+}
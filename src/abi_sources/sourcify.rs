@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use ethabi::Address;
+use ethers::utils::to_checksum;
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+
+use crate::chains::chains::Chain;
+
+use super::{AbiSource, ContractSource, SourceCode, ABI_SOURCE_SOURCIFY};
+
+pub const SOURCIFY_REPOSITORY_URL: &str = "https://repo.sourcify.dev";
+
+/// Sourcify repository, read from the public server, a mirror or a local copy of the
+/// repository. Full matches are preferred over partial matches.
+pub struct Sourcify {
+    pub client: Client,
+    pub url: String,
+}
+
+#[async_trait]
+impl AbiSource for Sourcify {
+    fn name(&self) -> &'static str {
+        ABI_SOURCE_SOURCIFY
+    }
+
+    async fn get_contract(&self, chain: &Chain, address: &str) -> Result<Option<ContractSource>> {
+        let address = to_checksum(&address.parse::<Address>()?, None);
+
+        for repository in ["full_match", "partial_match"] {
+            let path = format!(
+                "{}/contracts/{}/{}/{}/metadata.json",
+                self.url.trim_end_matches('/'),
+                repository,
+                chain.id,
+                address
+            );
+
+            let metadata = match self.get_file(&path).await? {
+                Some(metadata) => metadata,
+                None => continue,
+            };
+
+            let metadata: Value = serde_json::from_str(&metadata)?;
+
+            let abi = &metadata["output"]["abi"];
+
+            if !abi.is_array() {
+                continue;
+            }
+
+            // The compilation target maps the source file to the name of the contract.
+            let name = metadata["settings"]["compilationTarget"]
+                .as_object()
+                .and_then(|target| target.values().next())
+                .and_then(|name| name.as_str())
+                .map(|name| name.to_owned());
+
+            let directory = format!(
+                "{}/contracts/{}/{}/{}",
+                self.url.trim_end_matches('/'),
+                repository,
+                chain.id,
+                address
+            );
+
+            let source_code = self.get_source_code(&directory, &metadata).await?;
+
+            return Ok(Some(ContractSource {
+                abi: abi.to_string(),
+                name,
+                implementation: None,
+                source_code: Some(source_code),
+            }));
+        }
+
+        Ok(None)
+    }
+}
+
+impl Sourcify {
+    /// Reads the compiler settings from the metadata and the source files stored next to it.
+    async fn get_source_code(&self, directory: &str, metadata: &Value) -> Result<SourceCode> {
+        let mut files: BTreeMap<String, String> = BTreeMap::new();
+
+        if let Some(sources) = metadata["sources"].as_object() {
+            for (path, file) in sources {
+                let content = match file["content"].as_str() {
+                    Some(content) => Some(content.to_owned()),
+                    None => {
+                        self.get_file(&format!("{}/sources/{}", directory, path))
+                            .await?
+                    }
+                };
+
+                if let Some(content) = content {
+                    files.insert(path.clone(), content);
+                }
+            }
+        }
+
+        let constructor_arguments = self
+            .get_file(&format!("{}/constructor-args.txt", directory))
+            .await?
+            .map(|arguments| arguments.trim().trim_start_matches("0x").to_owned())
+            .filter(|arguments| !arguments.is_empty());
+
+        Ok(SourceCode {
+            compiler_version: metadata["compiler"]["version"]
+                .as_str()
+                .map(|version| version.to_owned()),
+            constructor_arguments,
+            evm_version: metadata["settings"]["evmVersion"]
+                .as_str()
+                .map(|version| version.to_owned()),
+            files,
+            license_type: None,
+            optimization_used: metadata["settings"]["optimizer"]["enabled"].as_bool(),
+            runs: metadata["settings"]["optimizer"]["runs"].as_i64(),
+        })
+    }
+
+    async fn get_file(&self, path: &str) -> Result<Option<String>> {
+        if !path.starts_with("http://") && !path.starts_with("https://") {
+            return Ok(std::fs::read_to_string(path).ok());
+        }
+
+        let response = self.client.get(path).send().await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        Ok(Some(response.error_for_status()?.text().await?))
+    }
+}
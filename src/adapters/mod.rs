@@ -0,0 +1,109 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Result;
+use ethabi::Address;
+use log::{info, warn};
+use serde::Deserialize;
+
+use crate::{
+    db::{db::Database, models::models::DatabaseContractAdapter},
+    utils::format_address,
+};
+
+/// Contracts of an adapter in a single chain.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdapterContracts {
+    pub id: String,
+    pub chain: String,
+    pub addresses: Vec<String>,
+}
+
+/// Mappings file, in JSON or TOML with a list of `[[adapters]]` tables.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdaptersFile {
+    pub adapters: Vec<AdapterContracts>,
+}
+
+/// Loads the adapters mappings from a `.json` or `.toml` file, or from every file of a
+/// directory. Each contract belongs to a single adapter, the last mapping found is kept.
+pub fn load_adapters(path: &str) -> Result<Vec<DatabaseContractAdapter>> {
+    let path = Path::new(path);
+
+    let files = if path.is_dir() {
+        let mut files: Vec<_> = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| {
+                matches!(
+                    file.extension().and_then(|extension| extension.to_str()),
+                    Some("json") | Some("toml")
+                )
+            })
+            .collect();
+
+        files.sort();
+
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut contracts_adapters: HashMap<(String, String), DatabaseContractAdapter> = HashMap::new();
+
+    for file in files {
+        let content = std::fs::read_to_string(&file)?;
+
+        let adapters_file: AdaptersFile =
+            match file.extension().and_then(|extension| extension.to_str()) {
+                Some("toml") => toml::from_str(&content)?,
+                _ => serde_json::from_str(&content)?,
+            };
+
+        for adapter in adapters_file.adapters {
+            let chain = adapter.chain.trim().to_lowercase();
+
+            for address in adapter.addresses {
+                let address = match address.trim().parse::<Address>() {
+                    Ok(address) => format_address(address),
+                    Err(_) => {
+                        warn!(
+                            "Adapters: Invalid address {} for adapter {} in {}.",
+                            address,
+                            adapter.id,
+                            file.display()
+                        );
+                        continue;
+                    }
+                };
+
+                contracts_adapters.insert(
+                    (address.clone(), chain.clone()),
+                    DatabaseContractAdapter {
+                        adapter_id: adapter.id.clone(),
+                        address,
+                        chain: chain.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    Ok(contracts_adapters.into_values().collect())
+}
+
+/// Replaces the adapters mappings of the chains found in the files, the mappings of those
+/// chains no longer in the files are deleted.
+pub async fn import_adapters(db: &Database, path: &str) -> Result<()> {
+    let contracts_adapters = load_adapters(path)?;
+
+    if !contracts_adapters.is_empty() {
+        db.replace_contracts_adapters(&contracts_adapters).await?;
+    }
+
+    info!(
+        "Adapters: Imported {} contracts adapters from {}.",
+        contracts_adapters.len(),
+        path
+    );
+
+    Ok(())
+}
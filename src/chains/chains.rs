@@ -10,6 +10,8 @@ pub struct Chain {
     pub supports_blocks_receipts: bool,
     pub public_rpc: &'static str,
     pub tokens_lists: &'static [&'static str],
+    pub wrapped_native: Option<&'static str>,
+    pub stablecoins: &'static [&'static str],
 }
 
 impl Chain {
@@ -23,6 +25,27 @@ impl Chain {
             supports_blocks_receipts: chain.supports_blocks_receipts,
             public_rpc: chain.public_rpc,
             tokens_lists: chain.tokens_lists,
+            wrapped_native: chain.wrapped_native,
+            stablecoins: chain.stablecoins,
+        }
+    }
+
+    /// Returns the RPCs configured for the chain in the `{CHAIN}_RPCS` environment variable as
+    /// a comma separated list, or the public RPC when none is configured.
+    pub fn get_rpcs(&self) -> Vec<String> {
+        let variable = format!("{}_RPCS", self.name.to_uppercase().replace('-', "_"));
+
+        let rpcs: Vec<String> = std::env::var(variable)
+            .unwrap_or_default()
+            .split(',')
+            .map(|rpc| rpc.trim().to_owned())
+            .filter(|rpc| !rpc.is_empty())
+            .collect();
+
+        if rpcs.is_empty() {
+            vec![self.public_rpc.to_owned()]
+        } else {
+            rpcs
         }
     }
 }
@@ -36,6 +59,12 @@ pub const ETHEREUM: Chain = Chain {
     supports_blocks_receipts: true,
     public_rpc: "https://eth.llamarpc.com",
     tokens_lists: &["https://raw.githubusercontent.com/llamafolio/llamafolio-tokens/master/ethereum/tokenlist.json", "https://raw.githubusercontent.com/viaprotocol/tokenlists/main/tokenlists/ethereum.json"],
+    wrapped_native: Some("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"),
+    stablecoins: &[
+        "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+        "0xdac17f958d2ee523a2206206994597c13d831ec7",
+        "0x6b175474e89094c44da98b954eedeac495271d0f",
+    ],
 };
 
 pub const POLYGON: Chain = Chain {
@@ -47,6 +76,12 @@ pub const POLYGON: Chain = Chain {
     supports_blocks_receipts: true,
     public_rpc: "https://polygon.llamarpc.com",
     tokens_lists: &["https://raw.githubusercontent.com/llamafolio/llamafolio-tokens/master/polygon/tokenlist.json", "https://raw.githubusercontent.com/viaprotocol/tokenlists/main/tokenlists/polygon.json"],
+    wrapped_native: Some("0x0d500b1d8e8ef31e21c99d1db9a6444d3adf1270"),
+    stablecoins: &[
+        "0x2791bca1f2de4661ed88a30c99a7a9449aa84174",
+        "0xc2132d05d31c914a87c6611c10748aeb04b58e8f",
+        "0x8f3cf7ad23cd3cadbd9735aff958023239c6a063",
+    ],
 };
 
 pub const FANTOM: Chain = Chain {
@@ -58,6 +93,12 @@ pub const FANTOM: Chain = Chain {
     supports_blocks_receipts: false,
     public_rpc: "https://rpc.ftm.tools",
     tokens_lists: &["https://raw.githubusercontent.com/llamafolio/llamafolio-tokens/master/fantom/tokenlist.json", "https://raw.githubusercontent.com/viaprotocol/tokenlists/main/tokenlists/ftm.json"],
+    wrapped_native: Some("0x21be370d5312f44cb42ce377bc9b8a0cef1a4c83"),
+    stablecoins: &[
+        "0x04068da6c83afcfa0e13ba15a6696662335d5b75",
+        "0x049d68029688eabf473097a2fc38ef61633a3c7a",
+        "0x8d11ec38a3eb5e956b052f67da8bdc9bef8abf3e",
+    ],
 };
 
 pub const BSC: Chain = Chain {
@@ -72,6 +113,12 @@ pub const BSC: Chain = Chain {
         "https://raw.githubusercontent.com/llamafolio/llamafolio-tokens/master/bsc/tokenlist.json",
         "https://raw.githubusercontent.com/viaprotocol/tokenlists/main/tokenlists/bsc.json",
     ],
+    wrapped_native: Some("0xbb4cdb9cbd36b01bd1cbaebf2de08d9173bc095c"),
+    stablecoins: &[
+        "0x55d398326f99059ff775485246999027b3197955",
+        "0x8ac76a51cc950d9822d68b83fe1ad97b32cd580d",
+        "0xe9e7cea3dedca5984780bafc599bd69add087d56",
+    ],
 };
 
 pub const GNOSIS: Chain = Chain {
@@ -86,6 +133,12 @@ pub const GNOSIS: Chain = Chain {
         "https://raw.githubusercontent.com/llamafolio/llamafolio-tokens/master/xdai/tokenlist.json",
         "https://raw.githubusercontent.com/viaprotocol/tokenlists/main/tokenlists/gnosis.json",
     ],
+    wrapped_native: Some("0xe91d153e0b41518a2ce8dd3d7944fa863463a97d"),
+    stablecoins: &[
+        "0xe91d153e0b41518a2ce8dd3d7944fa863463a97d",
+        "0xddafbb505ad214d7b80b1f830fccc89b60fb7a83",
+        "0x4ecaba5870353805a9f068101a40e0f32ed605c6",
+    ],
 };
 
 pub const OPTIMISM: Chain = Chain {
@@ -100,6 +153,12 @@ pub const OPTIMISM: Chain = Chain {
         "https://raw.githubusercontent.com/llamafolio/llamafolio-tokens/master/optimism/tokenlist.json",
         "https://raw.githubusercontent.com/viaprotocol/tokenlists/main/tokenlists/optimism.json",
     ],
+    wrapped_native: Some("0x4200000000000000000000000000000000000006"),
+    stablecoins: &[
+        "0x7f5c764cbc14f9669b88837ca1490cca17c31607",
+        "0x94b008aa00579c1307b0ef2c499ad98a8ce58e58",
+        "0xda10009cbd5d07dd0cecc66161fc93d7c9000da1",
+    ],
 };
 
 pub const ARBITRUM_ONE: Chain = Chain {
@@ -114,6 +173,12 @@ pub const ARBITRUM_ONE: Chain = Chain {
         "https://raw.githubusercontent.com/llamafolio/llamafolio-tokens/master/arbitrum/tokenlist.json",
         "https://raw.githubusercontent.com/viaprotocol/tokenlists/main/tokenlists/arbitrum.json",
     ],
+    wrapped_native: Some("0x82af49447d8a07e3bd95bd0d56f35241523fbab1"),
+    stablecoins: &[
+        "0xff970a61a04b1ca14834a43f5de4533ebddb5cc8",
+        "0xfd086bc7cd5c481dcc9c85ebe478a1c0b69fcbb9",
+        "0xda10009cbd5d07dd0cecc66161fc93d7c9000da1",
+    ],
 };
 
 pub const ARBITRUM_NOVA: Chain = Chain {
@@ -125,6 +190,8 @@ pub const ARBITRUM_NOVA: Chain = Chain {
     supports_blocks_receipts: false,
     public_rpc: "https://nova.arbitrum.io/rpc",
     tokens_lists: &[],
+    wrapped_native: Some("0x722e8bdd2ce80a4422e880164f2079488e115365"),
+    stablecoins: &[],
 };
 
 pub const MOONBEAM: Chain = Chain {
@@ -138,6 +205,8 @@ pub const MOONBEAM: Chain = Chain {
     tokens_lists: &[
         "https://raw.githubusercontent.com/viaprotocol/tokenlists/main/tokenlists/moonbeam.json",
     ],
+    wrapped_native: Some("0xacc15dc74880c9944775448304b263d191c6077f"),
+    stablecoins: &[],
 };
 
 pub const AVALANCHE: Chain = Chain {
@@ -152,6 +221,12 @@ pub const AVALANCHE: Chain = Chain {
         "https://raw.githubusercontent.com/llamafolio/llamafolio-tokens/master/avax/tokenlist.json",
         "https://raw.githubusercontent.com/viaprotocol/tokenlists/main/tokenlists/avax.json",
     ],
+    wrapped_native: Some("0xb31f66aa3c1e785363f0875a1b74e27b85fd66c7"),
+    stablecoins: &[
+        "0xb97ef9ef8734c71904d8002f8b6bc66dd9c48a6e",
+        "0xa7d7079b0fead91f3e65f86e8915cb59c1a4c664",
+        "0x9702230a8ea53601f5cd2dc00fdbc13d4df4a8c7",
+    ],
 };
 
 pub const BITTORRENT: Chain = Chain {
@@ -163,6 +238,8 @@ pub const BITTORRENT: Chain = Chain {
     supports_blocks_receipts: false,
     public_rpc: "https://rpc.bittorrentchain.io",
     tokens_lists: &[],
+    wrapped_native: Some("0x23181f21dea5936e24163ffaba4ea3b316b57f3c"),
+    stablecoins: &[],
 };
 
 pub const CELO: Chain = Chain {
@@ -177,6 +254,8 @@ pub const CELO: Chain = Chain {
         "https://raw.githubusercontent.com/llamafolio/llamafolio-tokens/master/celo/tokenlist.json",
         "https://raw.githubusercontent.com/viaprotocol/tokenlists/main/tokenlists/celo.json",
     ],
+    wrapped_native: None,
+    stablecoins: &["0x765de816845861e75a25fca122bb6898b8b1282a"],
 };
 
 pub static CHAINS: [Chain; 12] = [
@@ -2,7 +2,10 @@ use std::collections::HashMap;
 
 use clap::Parser;
 
-use crate::chains::chains::get_chains;
+use crate::{
+    abi_sources::sourcify::SOURCIFY_REPOSITORY_URL,
+    chains::chains::{get_chain, get_chains, Chain},
+};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -12,6 +15,57 @@ use crate::chains::chains::get_chains;
 pub struct EVMAbiFetcherArgs {
     #[arg(long, help = "Start log with debug", default_value_t = false)]
     pub debug: bool,
+
+    #[arg(
+        long,
+        help = "Comma separated chains to fetch ABIs for, all the chains by default",
+        value_delimiter = ','
+    )]
+    pub chain: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Import methods, events and errors signatures from a local file and exit"
+    )]
+    pub import_signatures: Option<String>,
+
+    #[arg(
+        long,
+        help = "Comma separated ABI sources in priority order, a chain can override it with the {CHAIN}_ABI_SOURCES variable",
+        default_value = "etherscan,sourcify,local"
+    )]
+    pub abi_sources: String,
+
+    #[arg(
+        long,
+        help = "Sourcify repository URL or local path",
+        default_value = SOURCIFY_REPOSITORY_URL
+    )]
+    pub sourcify_url: String,
+
+    #[arg(long, help = "Directory with ABIs stored as <chain>/<address>.json")]
+    pub abis_directory: Option<String>,
+
+    #[arg(
+        long,
+        help = "Amount of contracts fetched concurrently for each chain",
+        default_value_t = 4
+    )]
+    pub workers: usize,
+
+    #[arg(
+        long,
+        help = "Maximum requests per second to each block explorer",
+        default_value_t = 4.0
+    )]
+    pub explorer_rate_limit: f64,
+
+    #[arg(
+        long,
+        help = "Hours until an unverified contract is checked again",
+        default_value_t = 168
+    )]
+    pub unverified_recheck_hours: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -19,7 +73,15 @@ pub struct EVMAbiFetcherConfig {
     pub db_url: String,
     pub redis_url: String,
     pub debug: bool,
+    pub chains: Vec<Chain>,
     pub api_source_tokens: HashMap<String, String>,
+    pub import_signatures: Option<String>,
+    pub abi_sources: HashMap<String, Vec<String>>,
+    pub sourcify_url: String,
+    pub abis_directory: Option<String>,
+    pub workers: usize,
+    pub explorer_rate_limit: f64,
+    pub unverified_recheck_hours: i64,
 }
 
 impl EVMAbiFetcherConfig {
@@ -28,24 +90,67 @@ impl EVMAbiFetcherConfig {
 
         let mut api_source_tokens: HashMap<String, String> = HashMap::new();
 
-        let chains = get_chains();
+        let mut abi_sources: HashMap<String, Vec<String>> = HashMap::new();
+
+        let all_chains = get_chains();
+
+        let chains: Vec<Chain> = if args.chain.is_empty() {
+            all_chains.into_values().collect()
+        } else {
+            args.chain
+                .iter()
+                .map(|chain| get_chain(chain.trim().to_owned()))
+                .collect()
+        };
+
+        for key in chains.iter().map(|chain| chain.name.to_owned()) {
+            abi_sources.insert(
+                key.clone(),
+                get_abi_sources_for_chain(key.clone(), &args.abi_sources),
+            );
 
-        for (key, _) in chains {
             match get_abi_token_for_chain(key.clone()) {
                 Some(token) => api_source_tokens.insert(key, token),
                 None => continue,
             };
         }
 
+        if args.explorer_rate_limit.is_nan() || args.explorer_rate_limit <= 0.0 {
+            panic!("--explorer-rate-limit must be greater than 0.");
+        }
+
         Self {
             db_url: std::env::var("DATABASE_URL").expect("DATABASE_URL must be set."),
             redis_url: std::env::var("REDIS_URL").expect("REDIS_URL must be set."),
             debug: args.debug,
+            chains,
             api_source_tokens,
+            import_signatures: args.import_signatures,
+            abi_sources,
+            sourcify_url: args.sourcify_url,
+            abis_directory: args.abis_directory,
+            workers: args.workers,
+            explorer_rate_limit: args.explorer_rate_limit,
+            unverified_recheck_hours: args.unverified_recheck_hours,
         }
     }
 }
 
+pub fn get_abi_sources_for_chain(chain: String, default: &str) -> Vec<String> {
+    let variable = format!("{}_ABI_SOURCES", chain.to_uppercase().replace('-', "_"));
+
+    let sources = std::env::var(variable)
+        .ok()
+        .filter(|sources| !sources.trim().is_empty())
+        .unwrap_or_else(|| default.to_owned());
+
+    sources
+        .split(',')
+        .map(|source| source.trim().to_lowercase())
+        .filter(|source| !source.is_empty())
+        .collect()
+}
+
 pub fn get_abi_token_for_chain(chain: String) -> Option<String> {
     if chain == "ethereum" {
         let token = std::env::var("ETHERSCAN_TOKEN");
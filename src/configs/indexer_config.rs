@@ -37,8 +37,18 @@ pub struct EVMIndexerArgs {
     )]
     pub recalculate_indexed_blocks: bool,
 
-    #[arg(long, help = "Comma separated list of rpcs to use to fetch blocks.")]
-    pub rpcs: String,
+    #[arg(
+        long,
+        help = "Comma separated list of rpcs to use to fetch blocks. Defaults to the {CHAIN}_RPCS environment variable."
+    )]
+    pub rpcs: Option<String>,
+
+    #[arg(
+        long,
+        help = "Fetch and decode the revert reason of failed transactions.",
+        default_value_t = false
+    )]
+    pub revert_reasons: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +62,7 @@ pub struct EVMIndexerConfig {
     pub reset: bool,
     pub rpcs: Vec<String>,
     pub recalc_blocks_indexer: bool,
+    pub revert_reasons: bool,
 }
 
 impl EVMIndexerConfig {
@@ -66,7 +77,10 @@ impl EVMIndexerConfig {
 
         let chain = get_chain(chainname.clone());
 
-        let rpcs: Vec<String> = args.rpcs.split(",").map(|rpc| rpc.to_string()).collect();
+        let rpcs: Vec<String> = match args.rpcs {
+            Some(rpcs) => rpcs.split(",").map(|rpc| rpc.to_string()).collect(),
+            None => chain.get_rpcs(),
+        };
 
         Self {
             start_block: args.start_block,
@@ -78,6 +92,7 @@ impl EVMIndexerConfig {
             reset: args.reset,
             rpcs,
             recalc_blocks_indexer: args.recalculate_indexed_blocks,
+            revert_reasons: args.revert_reasons,
         }
     }
 }
@@ -18,6 +18,117 @@ pub struct EVMParserArgs {
         default_value_t = false
     )]
     pub erc20_balances: bool,
+
+    #[arg(
+        long,
+        help = "Start the erc20 approvals parser",
+        default_value_t = false
+    )]
+    pub erc20_approvals: bool,
+
+    #[arg(long, help = "Start the erc20 supply parser", default_value_t = false)]
+    pub erc20_supply: bool,
+
+    #[arg(
+        long,
+        help = "Start the ABI based logs decoder",
+        default_value_t = false
+    )]
+    pub decoded_logs: bool,
+
+    #[arg(
+        long,
+        help = "Start the ABI based transactions decoder",
+        default_value_t = false
+    )]
+    pub decoded_transactions: bool,
+
+    #[arg(
+        long,
+        help = "Start the uniswap v2 and v3 trades and liquidity parser",
+        default_value_t = false
+    )]
+    pub dex_trades: bool,
+
+    #[arg(
+        long,
+        help = "Start the dex and chainlink based token prices parser",
+        default_value_t = false
+    )]
+    pub token_prices: bool,
+
+    #[arg(
+        long,
+        help = "Start the spam tokens classifier",
+        default_value_t = false
+    )]
+    pub token_flags: bool,
+
+    #[arg(
+        long,
+        help = "Start the proxy upgrades parser",
+        default_value_t = false
+    )]
+    pub proxy_upgrades: bool,
+
+    #[arg(
+        long,
+        help = "Start the contracts bytecode and standards classifier",
+        default_value_t = false
+    )]
+    pub contracts_code: bool,
+
+    #[arg(
+        long,
+        help = "Start the ABI inference for unverified contracts",
+        default_value_t = false
+    )]
+    pub inferred_abis: bool,
+
+    #[arg(
+        long,
+        help = "Start the ENS names and reverse records parser",
+        default_value_t = false
+    )]
+    pub ens_names: bool,
+
+    #[arg(
+        long,
+        help = "Start the safes owners and executions parser",
+        default_value_t = false
+    )]
+    pub safes: bool,
+
+    #[arg(
+        long,
+        help = "JSON file with the chainlink price feeds used by the token prices parser"
+    )]
+    pub price_feeds: Option<String>,
+
+    #[arg(
+        long,
+        help = "JSON file with the stablecoins used as anchors by the token prices parser"
+    )]
+    pub price_anchors: Option<String>,
+
+    #[arg(
+        long,
+        help = "JSON file with the additional tokens lists used by the erc20 tokens parser"
+    )]
+    pub tokens_lists: Option<String>,
+
+    #[arg(
+        long,
+        help = "Seconds between each refresh of the tokens lists",
+        default_value_t = 3600
+    )]
+    pub tokens_lists_refresh: u64,
+
+    #[arg(
+        long,
+        help = "JSON or TOML file, or directory of files, with the adapters contracts to import"
+    )]
+    pub adapters: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +138,23 @@ pub struct EVMParserConfig {
     pub debug: bool,
     pub erc20_tokens: bool,
     pub erc20_balances: bool,
+    pub erc20_approvals: bool,
+    pub erc20_supply: bool,
+    pub decoded_logs: bool,
+    pub decoded_transactions: bool,
+    pub dex_trades: bool,
+    pub token_prices: bool,
+    pub token_flags: bool,
+    pub proxy_upgrades: bool,
+    pub contracts_code: bool,
+    pub inferred_abis: bool,
+    pub ens_names: bool,
+    pub safes: bool,
+    pub price_feeds: Option<String>,
+    pub price_anchors: Option<String>,
+    pub tokens_lists: Option<String>,
+    pub tokens_lists_refresh: u64,
+    pub adapters: Option<String>,
 }
 
 impl EVMParserConfig {
@@ -39,6 +167,23 @@ impl EVMParserConfig {
             debug: args.debug,
             erc20_tokens: args.erc20_tokens,
             erc20_balances: args.erc20_balances,
+            erc20_approvals: args.erc20_approvals,
+            erc20_supply: args.erc20_supply,
+            decoded_logs: args.decoded_logs,
+            decoded_transactions: args.decoded_transactions,
+            dex_trades: args.dex_trades,
+            token_prices: args.token_prices,
+            token_flags: args.token_flags,
+            proxy_upgrades: args.proxy_upgrades,
+            contracts_code: args.contracts_code,
+            inferred_abis: args.inferred_abis,
+            ens_names: args.ens_names,
+            safes: args.safes,
+            price_feeds: args.price_feeds,
+            price_anchors: args.price_anchors,
+            tokens_lists: args.tokens_lists,
+            tokens_lists_refresh: args.tokens_lists_refresh,
+            adapters: args.adapters,
         }
     }
 }
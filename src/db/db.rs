@@ -1,4 +1,7 @@
-use std::{cmp::min, collections::HashSet};
+use std::{
+    cmp::min,
+    collections::{HashMap, HashSet},
+};
 
 use anyhow::Result;
 use field_count::FieldCount;
@@ -7,18 +10,24 @@ use log::*;
 use redis::Commands;
 use sqlx::{
     postgres::{PgConnectOptions, PgPoolOptions},
-    ConnectOptions, QueryBuilder, Row,
+    ConnectOptions, Postgres, QueryBuilder, Row, Transaction,
 };
 
 use crate::chains::chains::Chain;
 
 use super::models::models::{
-    DatabaseBlock, DatabaseChainIndexedState, DatabaseContract, DatabaseContractInformation,
-    DatabaseLog, DatabaseMethod, DatabaseReceipt, DatabaseTransaction,
+    ActivityCursor, DatabaseAddress, DatabaseAddressActivity, DatabaseBlock,
+    DatabaseChainIndexedState, DatabaseContract, DatabaseContractAdapter,
+    DatabaseContractImplementation, DatabaseContractInformation, DatabaseContractSource,
+    DatabaseError, DatabaseEvent, DatabaseLog, DatabaseMethod, DatabaseReceipt,
+    DatabaseRevertReason, DatabaseTransaction,
 };
 
 pub const MAX_DIESEL_PARAM_SIZE: u16 = u16::MAX;
 
+/// Maximum size of the values sent in a single statement for tables storing large texts.
+pub const MAX_STATEMENT_BYTES: usize = 4 * 1024 * 1024;
+
 #[derive(Debug, Clone)]
 pub struct Database {
     pub chain: Chain,
@@ -74,12 +83,15 @@ impl Database {
         Ok(())
     }
 
+    /// Returns the contracts of the chain due to be fetched, both the ones never fetched and
+    /// the unverified ones scheduled to be checked again, the most used contracts first.
     pub async fn get_contracts_missing_parsed(&self) -> Result<Vec<DatabaseContract>> {
         let connection = self.get_connection();
 
         let rows = sqlx::query_as::<_, DatabaseContract>(
-            "SELECT * FROM contracts WHERE parsed = true LIMIT 500",
+            "SELECT block, chain, contract, creator, hash, parsed, verified FROM contracts WHERE chain = $1 AND (parsed = false OR verified = false) AND (next_attempt_at IS NULL OR next_attempt_at < now()) ORDER BY interactions DESC, next_attempt_at NULLS FIRST LIMIT 500",
         )
+        .bind(self.chain.name)
         .fetch_all(connection)
         .await;
 
@@ -89,6 +101,193 @@ impl Database {
         }
     }
 
+    /// Counts the transactions sent to and the logs emitted by the contracts pending to be
+    /// fetched, refreshing the contracts not counted in the last day first.
+    pub async fn update_contracts_interactions(&self) -> Result<()> {
+        let connection = self.get_connection();
+
+        sqlx::query(
+            "UPDATE contracts SET interactions = (SELECT count(*) FROM transactions WHERE transactions.to_address = contracts.contract AND transactions.chain = contracts.chain) + (SELECT count(*) FROM logs WHERE logs.address = contracts.contract AND logs.chain = contracts.chain), interactions_updated_at = now() WHERE chain = $1 AND (parsed = false OR verified = false) AND (interactions_updated_at IS NULL OR interactions_updated_at < now() - INTERVAL '1 day') ORDER BY interactions_updated_at NULLS FIRST LIMIT 1000",
+        )
+        .bind(self.chain.name)
+        .execute(connection)
+        .await
+        .expect("Unable to update contracts interactions into database");
+
+        Ok(())
+    }
+
+    pub async fn get_contracts_information(
+        &self,
+        contracts: &[(String, String)],
+    ) -> Result<Vec<DatabaseContractInformation>> {
+        let connection = self.get_connection();
+
+        let mut contracts_information = Vec::new();
+
+        if contracts.is_empty() {
+            return Ok(contracts_information);
+        }
+
+        let chunks = get_chunks(contracts.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "SELECT * FROM contracts_information WHERE abi IS NOT NULL AND (contract, chain) IN ",
+            );
+
+            query_builder.push_tuples(&contracts[start..end], |mut row, (contract, chain)| {
+                row.push_bind(contract.clone()).push_bind(chain.clone());
+            });
+
+            let rows = query_builder
+                .build_query_as::<DatabaseContractInformation>()
+                .fetch_all(connection)
+                .await;
+
+            match rows {
+                Ok(mut rows) => contracts_information.append(&mut rows),
+                Err(_) => continue,
+            }
+        }
+
+        Ok(contracts_information)
+    }
+
+    pub async fn get_methods(&self, methods: &[String]) -> Result<Vec<DatabaseMethod>> {
+        let connection = self.get_connection();
+
+        let mut db_methods = Vec::new();
+
+        if methods.is_empty() {
+            return Ok(db_methods);
+        }
+
+        let chunks = get_chunks(methods.len(), 1);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "SELECT * FROM methods WHERE signature IS NOT NULL AND method IN ",
+            );
+
+            query_builder.push_tuples(&methods[start..end], |mut row, method| {
+                row.push_bind(method.clone());
+            });
+
+            let rows = query_builder
+                .build_query_as::<DatabaseMethod>()
+                .fetch_all(connection)
+                .await;
+
+            match rows {
+                Ok(mut rows) => db_methods.append(&mut rows),
+                Err(_) => continue,
+            }
+        }
+
+        Ok(db_methods)
+    }
+
+    pub async fn get_events(&self, topics: &[String]) -> Result<Vec<DatabaseEvent>> {
+        let connection = self.get_connection();
+
+        let mut db_events = Vec::new();
+
+        if topics.is_empty() {
+            return Ok(db_events);
+        }
+
+        let chunks = get_chunks(topics.len(), 1);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new("SELECT * FROM events WHERE topic IN ");
+
+            query_builder.push_tuples(&topics[start..end], |mut row, topic| {
+                row.push_bind(topic.clone());
+            });
+
+            let rows = query_builder
+                .build_query_as::<DatabaseEvent>()
+                .fetch_all(connection)
+                .await;
+
+            match rows {
+                Ok(mut rows) => db_events.append(&mut rows),
+                Err(_) => continue,
+            }
+        }
+
+        Ok(db_events)
+    }
+
+    /// Adapters of the contracts called by an address or that emitted logs in its
+    /// transactions, including the address itself when it is a contract of an adapter.
+    pub async fn get_adapters_by_address(
+        &self,
+        address: &str,
+    ) -> Result<Vec<DatabaseContractAdapter>> {
+        let connection = self.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseContractAdapter>(
+            "SELECT * FROM contracts_adapters WHERE address = $1 OR (address, chain) IN (SELECT to_address, chain FROM transactions WHERE from_address = $1 UNION SELECT logs.address, logs.chain FROM logs INNER JOIN transactions ON logs.hash = transactions.hash WHERE transactions.from_address = $1)",
+        )
+        .bind(address.to_lowercase())
+        .fetch_all(connection)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Adapters of the contract called by a transaction and of the contracts that emitted its
+    /// logs.
+    pub async fn get_adapters_by_transaction(
+        &self,
+        hash: &str,
+    ) -> Result<Vec<DatabaseContractAdapter>> {
+        let connection = self.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseContractAdapter>(
+            "SELECT * FROM contracts_adapters WHERE (address, chain) IN (SELECT to_address, chain FROM transactions WHERE hash = $1 UNION SELECT address, chain FROM logs WHERE hash = $1)",
+        )
+        .bind(hash.to_lowercase())
+        .fetch_all(connection)
+        .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn get_errors(&self, selectors: &[String]) -> Result<Vec<DatabaseError>> {
+        let connection = self.get_connection();
+
+        let mut db_errors = Vec::new();
+
+        if selectors.is_empty() {
+            return Ok(db_errors);
+        }
+
+        let chunks = get_chunks(selectors.len(), 1);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new("SELECT * FROM errors WHERE selector IN ");
+
+            query_builder.push_tuples(&selectors[start..end], |mut row, selector| {
+                row.push_bind(selector.clone());
+            });
+
+            let rows = query_builder
+                .build_query_as::<DatabaseError>()
+                .fetch_all(connection)
+                .await;
+
+            match rows {
+                Ok(mut rows) => db_errors.append(&mut rows),
+                Err(_) => continue,
+            }
+        }
+
+        Ok(db_errors)
+    }
+
     pub async fn get_indexed_blocks(&self) -> Result<HashSet<i64>> {
         let mut connection = self.redis.get_connection().unwrap();
 
@@ -125,10 +324,34 @@ impl Database {
             self.store_contracts(&contracts).await.unwrap();
         }
 
+        // The addresses counters are only added for the transactions not stored before and in
+        // the same database transaction, so blocks indexed again are not counted twice.
+        let mut db_transaction = self.get_connection().begin().await.unwrap();
+
+        let new_transactions = self
+            .get_new_transactions(&mut db_transaction, transactions)
+            .await
+            .unwrap();
+
+        let addresses = DatabaseAddress::from_batch(&new_transactions, contracts);
+
+        if !addresses.is_empty() {
+            self.store_addresses(&mut db_transaction, &addresses)
+                .await
+                .unwrap();
+        }
+
         if transactions.len() > 0 {
-            self.store_transactions(&transactions).await.unwrap();
+            self.store_transactions(&mut db_transaction, &transactions)
+                .await
+                .unwrap();
         }
 
+        db_transaction
+            .commit()
+            .await
+            .expect("Unable to store transactions into database");
+
         if receipts.len() > 0 {
             self.store_transactions_receipts(&receipts).await.unwrap();
         }
@@ -137,21 +360,212 @@ impl Database {
             self.store_transactions_logs(&logs).await.unwrap();
         }
 
+        let activity = get_batch_activity(transactions, logs);
+
+        if !activity.is_empty() {
+            self.store_address_activity(&activity).await.unwrap();
+        }
+
         if blocks.len() > 0 {
             self.store_blocks(&blocks).await.unwrap();
         }
 
         info!(
-            "Inserted: blocks ({}) transactions ({}) receipts ({}) logs ({}) contracts ({}) for chain {}",
+            "Inserted: blocks ({}) transactions ({}) receipts ({}) logs ({}) contracts ({}) addresses ({}) for chain {}",
             blocks.len(),
             transactions.len(),
             receipts.len(),
             logs.len(),
             contracts.len(),
+            addresses.len(),
             self.chain.name.clone()
         );
     }
 
+    /// Returns the transactions of a batch that are not stored yet.
+    async fn get_new_transactions(
+        &self,
+        db_transaction: &mut Transaction<'_, Postgres>,
+        transactions: &[DatabaseTransaction],
+    ) -> Result<Vec<DatabaseTransaction>> {
+        let mut stored: HashSet<String> = HashSet::new();
+
+        if transactions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let chunks = get_chunks(transactions.len(), 1);
+
+        for (start, end) in chunks {
+            let mut query_builder =
+                QueryBuilder::new("SELECT hash FROM transactions WHERE hash IN ");
+
+            query_builder.push_tuples(&transactions[start..end], |mut row, transaction| {
+                row.push_bind(transaction.hash.clone());
+            });
+
+            let rows = query_builder
+                .build_query_as::<(String,)>()
+                .fetch_all(&mut *db_transaction)
+                .await?;
+
+            stored.extend(rows.into_iter().map(|(hash,)| hash));
+        }
+
+        Ok(transactions
+            .iter()
+            .filter(|transaction| !stored.contains(&transaction.hash))
+            .cloned()
+            .collect())
+    }
+
+    /// Merges the activity of a batch into the stored addresses. Counters are added, so only
+    /// the transactions not stored before must be included. Addresses seen before as contracts
+    /// are flagged from the `contracts` table.
+    async fn store_addresses(
+        &self,
+        db_transaction: &mut Transaction<'_, Postgres>,
+        addresses: &[DatabaseAddress],
+    ) -> Result<()> {
+        let chunks = get_chunks(addresses.len(), DatabaseAddress::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "INSERT INTO addresses (address, chain, contract, first_seen_block, last_seen_block, nonce, tx_in, tx_out) ",
+            );
+
+            query_builder.push_values(&addresses[start..end], |mut row, address| {
+                row.push_bind(address.address.clone())
+                    .push_bind(address.chain.clone())
+                    .push_bind(address.contract)
+                    .push_bind(address.first_seen_block)
+                    .push_bind(address.last_seen_block)
+                    .push_bind(address.nonce)
+                    .push_bind(address.tx_in)
+                    .push_bind(address.tx_out);
+            });
+
+            query_builder.push(" ON CONFLICT (address, chain) DO UPDATE SET contract = addresses.contract OR excluded.contract, first_seen_block = least(addresses.first_seen_block, excluded.first_seen_block), last_seen_block = greatest(addresses.last_seen_block, excluded.last_seen_block), nonce = greatest(addresses.nonce, excluded.nonce), tx_in = addresses.tx_in + excluded.tx_in, tx_out = addresses.tx_out + excluded.tx_out");
+
+            let query = query_builder.build();
+
+            query
+                .execute(&mut *db_transaction)
+                .await
+                .expect("Unable to store addresses into database");
+        }
+
+        let chunks = get_chunks(addresses.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPDATE addresses SET contract = true WHERE contract = false AND (address, chain) IN (SELECT contract, chain FROM contracts WHERE (contract, chain) IN ",
+            );
+
+            query_builder.push_tuples(&addresses[start..end], |mut row, address| {
+                row.push_bind(address.address.clone())
+                    .push_bind(address.chain.clone());
+            });
+
+            query_builder.push(")");
+
+            let query = query_builder.build();
+
+            query
+                .execute(&mut *db_transaction)
+                .await
+                .expect("Unable to update addresses into database");
+        }
+
+        Ok(())
+    }
+
+    pub async fn store_address_activity(&self, activity: &[DatabaseAddressActivity]) -> Result<()> {
+        let connection = self.get_connection();
+
+        let chunks = get_chunks(activity.len(), DatabaseAddressActivity::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO address_activity (address, amount, block_number, chain, counterparty, hash, kind, log_index, token) ",
+            );
+
+            query_builder.push_values(&activity[start..end], |mut row, entry| {
+                row.push_bind(entry.address.clone())
+                    .push_bind(entry.amount.clone())
+                    .push_bind(entry.block_number)
+                    .push_bind(entry.chain.clone())
+                    .push_bind(entry.counterparty.clone())
+                    .push_bind(entry.hash.clone())
+                    .push_bind(entry.kind.clone())
+                    .push_bind(entry.log_index)
+                    .push_bind(entry.token.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store address activity into database");
+        }
+
+        Ok(())
+    }
+
+    /// Returns a page of the activity of an address from the newest entries, starting after the
+    /// cursor when given. The cursor of the next page is returned while there are more entries.
+    pub async fn get_address_activity(
+        &self,
+        address: &str,
+        chain: &str,
+        cursor: Option<&ActivityCursor>,
+        limit: i64,
+    ) -> Result<(Vec<DatabaseAddressActivity>, Option<ActivityCursor>)> {
+        let connection = self.get_connection();
+
+        let mut query_builder =
+            QueryBuilder::new("SELECT * FROM address_activity WHERE address = ");
+
+        query_builder
+            .push_bind(address.to_lowercase())
+            .push(" AND chain = ")
+            .push_bind(chain.to_owned());
+
+        if let Some(cursor) = cursor {
+            query_builder
+                .push(" AND (block_number, hash, log_index, kind) < (")
+                .push_bind(cursor.block_number)
+                .push(", ")
+                .push_bind(cursor.hash.clone())
+                .push(", ")
+                .push_bind(cursor.log_index)
+                .push(", ")
+                .push_bind(cursor.kind.clone())
+                .push(")");
+        }
+
+        // One more entry is requested to know if there is a next page.
+        query_builder
+            .push(" ORDER BY block_number DESC, hash DESC, log_index DESC, kind DESC LIMIT ")
+            .push_bind(limit + 1);
+
+        let mut activity = query_builder
+            .build_query_as::<DatabaseAddressActivity>()
+            .fetch_all(connection)
+            .await?;
+
+        let next = if activity.len() as i64 > limit {
+            activity.truncate(limit as usize);
+
+            activity.last().map(ActivityCursor::from_activity)
+        } else {
+            None
+        };
+
+        Ok((activity, next))
+    }
+
     async fn store_blocks(&self, blocks: &Vec<DatabaseBlock>) -> Result<()> {
         let connection = self.get_connection();
 
@@ -195,9 +609,11 @@ impl Database {
         Ok(())
     }
 
-    async fn store_transactions(&self, transactions: &Vec<DatabaseTransaction>) -> Result<()> {
-        let connection = self.get_connection();
-
+    async fn store_transactions(
+        &self,
+        db_transaction: &mut Transaction<'_, Postgres>,
+        transactions: &Vec<DatabaseTransaction>,
+    ) -> Result<()> {
         let chunks = get_chunks(transactions.len(), DatabaseTransaction::field_count());
 
         for (start, end) in chunks {
@@ -226,7 +642,7 @@ impl Database {
             let query = query_builder.build();
 
             query
-                .execute(connection)
+                .execute(&mut *db_transaction)
                 .await
                 .expect("Unable to store transactions into database");
         }
@@ -336,7 +752,7 @@ impl Database {
 
         for (start, end) in chunks {
             let mut query_builder = QueryBuilder::new(
-                "UPSERT INTO contracts_information (chain, contract, abi, name, verified) ",
+                "UPSERT INTO contracts_information (chain, contract, abi, name, verified, implementation, inferred) ",
             );
 
             query_builder.push_values(
@@ -346,7 +762,9 @@ impl Database {
                         .push_bind(contract_information.contract.clone())
                         .push_bind(contract_information.abi.clone())
                         .push_bind(contract_information.name.clone())
-                        .push_bind(contract_information.verified.clone());
+                        .push_bind(contract_information.verified.clone())
+                        .push_bind(contract_information.implementation.clone())
+                        .push_bind(contract_information.inferred);
                 },
             );
 
@@ -367,11 +785,13 @@ impl Database {
         let chunks = get_chunks(methods.len(), DatabaseMethod::field_count());
 
         for (start, end) in chunks {
-            let mut query_builder = QueryBuilder::new("UPSERT INTO methods (method, name) ");
+            let mut query_builder =
+                QueryBuilder::new("UPSERT INTO methods (method, name, signature) ");
 
             query_builder.push_values(&methods[start..end], |mut row, method| {
                 row.push_bind(method.method.clone())
-                    .push_bind(method.name.clone());
+                    .push_bind(method.name.clone())
+                    .push_bind(method.signature.clone());
             });
 
             let query = query_builder.build();
@@ -385,6 +805,93 @@ impl Database {
         Ok(())
     }
 
+    pub async fn store_events(&self, events: &[DatabaseEvent]) -> Result<()> {
+        let connection = self.get_connection();
+
+        let chunks = get_chunks(events.len(), DatabaseEvent::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder =
+                QueryBuilder::new("UPSERT INTO events (abi, indexed, name, signature, topic) ");
+
+            query_builder.push_values(&events[start..end], |mut row, event| {
+                row.push_bind(event.abi.clone())
+                    .push_bind(event.indexed.clone())
+                    .push_bind(event.name.clone())
+                    .push_bind(event.signature.clone())
+                    .push_bind(event.topic.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store events into database");
+        }
+
+        Ok(())
+    }
+
+    pub async fn store_errors(&self, errors: &[DatabaseError]) -> Result<()> {
+        let connection = self.get_connection();
+
+        let chunks = get_chunks(errors.len(), DatabaseError::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder =
+                QueryBuilder::new("UPSERT INTO errors (name, selector, signature) ");
+
+            query_builder.push_values(&errors[start..end], |mut row, error| {
+                row.push_bind(error.name.clone())
+                    .push_bind(error.selector.clone())
+                    .push_bind(error.signature.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store errors into database");
+        }
+
+        Ok(())
+    }
+
+    pub async fn store_revert_reasons(
+        &self,
+        revert_reasons: &[DatabaseRevertReason],
+    ) -> Result<()> {
+        let connection = self.get_connection();
+
+        let chunks = get_chunks(revert_reasons.len(), DatabaseRevertReason::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO revert_reasons (chain, data, hash, kind, reason, signature) ",
+            );
+
+            query_builder.push_values(&revert_reasons[start..end], |mut row, revert_reason| {
+                row.push_bind(revert_reason.chain.clone())
+                    .push_bind(revert_reason.data.clone())
+                    .push_bind(revert_reason.hash.clone())
+                    .push_bind(revert_reason.kind.clone())
+                    .push_bind(revert_reason.reason.clone())
+                    .push_bind(revert_reason.signature.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store revert reasons into database");
+        }
+
+        Ok(())
+    }
+
     pub async fn store_indexed_blocks(&self, blocks: &Vec<i64>) -> Result<()> {
         let mut connection = self.redis.get_connection().unwrap();
 
@@ -462,6 +969,273 @@ impl Database {
         Ok(())
     }
 
+    /// Schedules the next attempt of the contracts that failed to be fetched with an
+    /// exponential backoff.
+    pub async fn update_contracts_failed(&self, contracts: &[DatabaseContract]) -> Result<()> {
+        let connection = self.get_connection();
+
+        let chunks = get_chunks(contracts.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPDATE contracts SET attempts = attempts + 1, next_attempt_at = now() + INTERVAL '1 minute' * power(2, least(attempts, 12))::INT WHERE (contract, chain) IN ",
+            );
+
+            query_builder.push_tuples(&contracts[start..end], |mut row, contract| {
+                row.push_bind(contract.contract.clone())
+                    .push_bind(contract.chain.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to update contracts into database");
+        }
+
+        Ok(())
+    }
+
+    /// Resets the attempts of the fetched contracts, unverified contracts are checked again
+    /// after the given amount of hours.
+    pub async fn update_contracts_fetched(
+        &self,
+        contracts: &[DatabaseContract],
+        recheck_hours: i64,
+    ) -> Result<()> {
+        let connection = self.get_connection();
+
+        let chunks = get_chunks(contracts.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPDATE contracts SET abi_inferred = false, attempts = 0, next_attempt_at = CASE WHEN verified THEN NULL ELSE now() + INTERVAL '1 hour' * ",
+            );
+
+            query_builder
+                .push_bind(recheck_hours)
+                .push(" END WHERE (contract, chain) IN ");
+
+            query_builder.push_tuples(&contracts[start..end], |mut row, contract| {
+                row.push_bind(contract.contract.clone())
+                    .push_bind(contract.chain.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to update contracts into database");
+        }
+
+        Ok(())
+    }
+
+    pub async fn store_contracts_implementations(
+        &self,
+        implementations: &[DatabaseContractImplementation],
+    ) -> Result<()> {
+        let connection = self.get_connection();
+
+        let chunks = get_chunks(
+            implementations.len(),
+            DatabaseContractImplementation::field_count(),
+        );
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO contracts_implementations (block, chain, contract, implementation, standard) ",
+            );
+
+            query_builder.push_values(&implementations[start..end], |mut row, implementation| {
+                row.push_bind(implementation.block)
+                    .push_bind(implementation.chain.clone())
+                    .push_bind(implementation.contract.clone())
+                    .push_bind(implementation.implementation.clone())
+                    .push_bind(implementation.standard.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store contracts implementations into database");
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the adapters mappings of the chains included in `contracts_adapters`, the
+    /// mappings removed from those chains are deleted.
+    pub async fn replace_contracts_adapters(
+        &self,
+        contracts_adapters: &[DatabaseContractAdapter],
+    ) -> Result<()> {
+        let mut transaction = self.get_connection().begin().await?;
+
+        let mut chains: Vec<String> = contracts_adapters
+            .iter()
+            .map(|contract_adapter| contract_adapter.chain.clone())
+            .collect();
+
+        chains.sort();
+        chains.dedup();
+
+        sqlx::query("DELETE FROM contracts_adapters WHERE chain = ANY($1)")
+            .bind(chains)
+            .execute(&mut transaction)
+            .await
+            .expect("Unable to delete contracts adapters from database");
+
+        let chunks = get_chunks(
+            contracts_adapters.len(),
+            DatabaseContractAdapter::field_count(),
+        );
+
+        for (start, end) in chunks {
+            let mut query_builder =
+                QueryBuilder::new("UPSERT INTO contracts_adapters (adapter_id, address, chain) ");
+
+            query_builder.push_values(
+                &contracts_adapters[start..end],
+                |mut row, contract_adapter| {
+                    row.push_bind(contract_adapter.adapter_id.clone())
+                        .push_bind(contract_adapter.address.clone())
+                        .push_bind(contract_adapter.chain.clone());
+                },
+            );
+
+            let query = query_builder.build();
+
+            query
+                .execute(&mut transaction)
+                .await
+                .expect("Unable to store contracts adapters into database");
+        }
+
+        transaction
+            .commit()
+            .await
+            .expect("Unable to store contracts adapters into database");
+
+        Ok(())
+    }
+
+    pub async fn store_contracts_source(&self, sources: &[DatabaseContractSource]) -> Result<()> {
+        let connection = self.get_connection();
+
+        // Verified sources can include hundreds of files, so batches are also limited by size.
+        let sizes: Vec<usize> = sources
+            .iter()
+            .map(|source| {
+                source.files.len()
+                    + source.source.len()
+                    + source.constructor_arguments.as_ref().map_or(0, String::len)
+                    + source
+                        .decoded_constructor_arguments
+                        .as_ref()
+                        .map_or(0, String::len)
+            })
+            .collect();
+
+        let chunks = get_chunks_by_size(
+            &sizes,
+            DatabaseContractSource::field_count(),
+            MAX_STATEMENT_BYTES,
+        );
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO contracts_source (chain, compiler_version, constructor_arguments, contract, decoded_constructor_arguments, evm_version, files, license_type, optimization_used, runs, source) ",
+            );
+
+            query_builder.push_values(&sources[start..end], |mut row, source| {
+                row.push_bind(source.chain.clone())
+                    .push_bind(source.compiler_version.clone())
+                    .push_bind(source.constructor_arguments.clone())
+                    .push_bind(source.contract.clone())
+                    .push_bind(source.decoded_constructor_arguments.clone())
+                    .push_unseparated("::JSONB")
+                    .push_bind(source.evm_version.clone())
+                    .push_bind(source.files.clone())
+                    .push_unseparated("::JSONB")
+                    .push_bind(source.license_type.clone())
+                    .push_bind(source.optimization_used)
+                    .push_bind(source.runs)
+                    .push_bind(source.source.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store contracts source into database");
+        }
+
+        Ok(())
+    }
+
+    pub async fn update_logs_parsed(&self, column: &str, logs: &[DatabaseLog]) -> Result<()> {
+        let connection = self.get_connection();
+
+        // Each log is identified by the (hash, log_index) pair.
+        let chunks = get_chunks(logs.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(format!(
+                "UPDATE logs SET {} = true WHERE (hash, log_index) IN ",
+                column
+            ));
+
+            query_builder.push_tuples(&logs[start..end], |mut row, log| {
+                row.push_bind(log.hash.clone()).push_bind(log.log_index);
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to update parsed logs into database");
+        }
+
+        Ok(())
+    }
+
+    pub async fn update_transactions_parsed(
+        &self,
+        column: &str,
+        transactions: &[DatabaseTransaction],
+    ) -> Result<()> {
+        let connection = self.get_connection();
+
+        let chunks = get_chunks(transactions.len(), 1);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(format!(
+                "UPDATE transactions SET {} = true WHERE hash IN ",
+                column
+            ));
+
+            query_builder.push_tuples(&transactions[start..end], |mut row, transaction| {
+                row.push_bind(transaction.hash.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to update parsed transactions into database");
+        }
+
+        Ok(())
+    }
+
     pub async fn delete_indexed_blocks(&self) -> Result<()> {
         let mut connection = self.redis.get_connection().unwrap();
 
@@ -488,3 +1262,56 @@ pub fn get_chunks(num_items_to_insert: usize, column_count: usize) -> Vec<(usize
     }
     chunks
 }
+
+/// Splits the items like `get_chunks` while keeping the sum of the items sizes of each chunk
+/// under `max_bytes`, an item larger than the limit is sent alone.
+pub fn get_chunks_by_size(
+    sizes: &[usize],
+    column_count: usize,
+    max_bytes: usize,
+) -> Vec<(usize, usize)> {
+    let max_item_size = MAX_DIESEL_PARAM_SIZE as usize / column_count;
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut bytes = 0;
+    for (i, size) in sizes.iter().enumerate() {
+        if i > start && (bytes + size > max_bytes || i - start >= max_item_size) {
+            chunks.push((start, i));
+            start = i;
+            bytes = 0;
+        }
+        bytes += size;
+    }
+    if start < sizes.len() {
+        chunks.push((start, sizes.len()));
+    }
+    chunks
+}
+
+/// Activity of the transactions of a batch and of the contracts that emitted their logs.
+fn get_batch_activity(
+    transactions: &[DatabaseTransaction],
+    logs: &[DatabaseLog],
+) -> Vec<DatabaseAddressActivity> {
+    let mut activity: Vec<DatabaseAddressActivity> = transactions
+        .iter()
+        .flat_map(DatabaseAddressActivity::from_transaction)
+        .collect();
+
+    let senders: HashMap<&String, &DatabaseTransaction> = transactions
+        .iter()
+        .map(|transaction| (&transaction.hash, transaction))
+        .collect();
+
+    for log in logs {
+        if let Some(transaction) = senders.get(&log.hash) {
+            activity.push(DatabaseAddressActivity::from_log(
+                log,
+                transaction.block_number,
+                &transaction.from_address,
+            ));
+        }
+    }
+
+    activity
+}
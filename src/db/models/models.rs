@@ -1,9 +1,22 @@
-use ethers::types::{Block, Log, Transaction, TransactionReceipt, H160};
+use std::{
+    cmp::{max, min},
+    collections::HashMap,
+};
+
+use ethabi::{AbiError, Event, Function, Param, ParamType, RawLog, Token};
+use ethers::{
+    abi::{ErrorExt, EventExt, FunctionExt},
+    types::{Block, Log, Transaction, TransactionReceipt, H160, H256},
+    utils::id,
+};
 use field_count::FieldCount;
 
-use crate::utils::{
-    format_address, format_bytes, format_bytes_slice, format_hash, format_nonce, format_number,
-    format_small_number,
+use crate::{
+    abi_sources::SourceCode,
+    utils::{
+        format_address, format_bytes, format_bytes_slice, format_hash, format_nonce, format_number,
+        format_small_number, format_token, parse_signature,
+    },
 };
 
 #[derive(Debug, Clone, FieldCount)]
@@ -129,7 +142,7 @@ pub fn byte4_from_input(input: &String) -> [u8; 4] {
     return byte4;
 }
 
-#[derive(Debug, Clone, FieldCount)]
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
 pub struct DatabaseTransaction {
     pub block_hash: String,
     pub block_number: i64,
@@ -304,21 +317,552 @@ impl DatabaseLog {
             erc20_transfers_parsed: false,
         }
     }
+
+    pub fn to_raw_log(&self) -> Option<RawLog> {
+        let mut topics: Vec<H256> = Vec::new();
+
+        for topic in &self.topics {
+            match topic {
+                Some(topic) => match topic.parse::<H256>() {
+                    Ok(topic) => topics.push(topic),
+                    Err(_) => return None,
+                },
+                None => return None,
+            }
+        }
+
+        let data = match hex::decode(self.data.trim_start_matches("0x")) {
+            Ok(data) => data,
+            Err(_) => return None,
+        };
+
+        Some(RawLog { topics, data })
+    }
 }
 
-#[derive(Debug, Clone, FieldCount)]
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DatabaseLogWithBlock {
+    #[sqlx(flatten)]
+    pub log: DatabaseLog,
+    pub block_number: i64,
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
 pub struct DatabaseMethod {
     pub method: String,
     pub name: String,
+    pub signature: Option<String>,
 }
 
-#[derive(Debug, Clone, FieldCount)]
+impl DatabaseMethod {
+    pub fn from_abi(function: &Function) -> Self {
+        Self {
+            method: format!("0x{}", hex::encode(function.short_signature())),
+            name: function.name.clone(),
+            signature: Some(function.abi_signature()),
+        }
+    }
+
+    /// The selector is computed from the canonical signature, so lines with spaces or type
+    /// aliases like `uint` are stored as their canonical form.
+    pub fn from_signature(signature: &str) -> Option<Self> {
+        let (name, inputs) = parse_signature(signature)?;
+
+        let name = name.trim().to_owned();
+
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return None;
+        }
+
+        let inputs: Vec<String> = inputs.iter().map(|input| input.to_string()).collect();
+
+        let signature = format!("{}({})", name, inputs.join(","));
+
+        Some(Self {
+            method: format!("0x{}", hex::encode(id(&signature))),
+            name,
+            signature: Some(signature),
+        })
+    }
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseEvent {
+    pub abi: String,
+    pub indexed: Vec<bool>,
+    pub name: String,
+    pub signature: String,
+    pub topic: String,
+}
+
+impl DatabaseEvent {
+    pub fn from_abi(event: &Event) -> Self {
+        Self {
+            abi: serde_json::to_string(event).unwrap(),
+            indexed: event.inputs.iter().map(|input| input.indexed).collect(),
+            name: event.name.clone(),
+            signature: event.abi_signature(),
+            topic: format_hash(event.signature()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseError {
+    pub name: String,
+    pub selector: String,
+    pub signature: String,
+}
+
+impl DatabaseError {
+    pub fn from_abi(error: &AbiError) -> Self {
+        Self {
+            name: error.name.clone(),
+            selector: format!("0x{}", hex::encode(error.selector())),
+            signature: error.abi_signature(),
+        }
+    }
+
+    /// Signatures don't include the arguments names, so they are named by position.
+    pub fn to_abi(&self) -> Option<AbiError> {
+        let (name, inputs) = parse_signature(&self.signature)?;
+
+        Some(AbiError {
+            name,
+            inputs: inputs
+                .into_iter()
+                .enumerate()
+                .map(|(i, kind)| Param {
+                    name: format!("arg{}", i),
+                    kind,
+                    internal_type: None,
+                })
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseRevertReason {
+    pub chain: String,
+    pub data: String,
+    pub hash: String,
+    pub kind: String,
+    pub reason: String,
+    pub signature: Option<String>,
+}
+
+impl DatabaseRevertReason {
+    /// Decodes the revert data of a failed transaction. Besides the builtin `Error(string)` and
+    /// `Panic(uint256)` errors, the data is matched against the custom errors known for its
+    /// selector. When nothing matches, the error message returned by the node is kept instead.
+    pub fn from_data(
+        transaction: &DatabaseTransaction,
+        data: &[u8],
+        message: Option<String>,
+        errors: &[AbiError],
+    ) -> Self {
+        let (kind, reason, signature) = match decode_revert_data(data, errors) {
+            Some(decoded) => decoded,
+            None if data.is_empty() => ("empty", message.unwrap_or_default(), None),
+            None => ("unknown", message.unwrap_or_default(), None),
+        };
+
+        Self {
+            chain: transaction.chain.clone(),
+            data: format_bytes_slice(data),
+            hash: transaction.hash.clone(),
+            kind: kind.to_owned(),
+            reason,
+            signature,
+        }
+    }
+}
+
+fn decode_revert_data(
+    data: &[u8],
+    errors: &[AbiError],
+) -> Option<(&'static str, String, Option<String>)> {
+    if data.len() < 4 {
+        return None;
+    }
+
+    let (selector, input) = data.split_at(4);
+
+    // keccak256("Error(string)")
+    if selector == [0x08, 0xc3, 0x79, 0xa0] {
+        return match ethabi::decode(&[ParamType::String], input).ok()?.first() {
+            Some(Token::String(reason)) => {
+                Some(("error", reason.clone(), Some(String::from("Error(string)"))))
+            }
+            _ => None,
+        };
+    }
+
+    // keccak256("Panic(uint256)")
+    if selector == [0x4e, 0x48, 0x7b, 0x71] {
+        return match ethabi::decode(&[ParamType::Uint(256)], input).ok()?.first() {
+            Some(Token::Uint(code)) => Some((
+                "panic",
+                format!(
+                    "Panic(0x{:02x}): {}",
+                    code,
+                    panic_description(code.low_u64())
+                ),
+                Some(String::from("Panic(uint256)")),
+            )),
+            _ => None,
+        };
+    }
+
+    for error in errors.iter().filter(|error| error.selector() == selector) {
+        let tokens = match error.decode(input) {
+            Ok(tokens) => tokens,
+            Err(_) => continue,
+        };
+
+        let params: Vec<String> = error
+            .inputs
+            .iter()
+            .zip(tokens.iter())
+            .map(|(param, token)| format!("{}={}", param.name, format_token(token)))
+            .collect();
+
+        return Some((
+            "custom",
+            format!("{}({})", error.name, params.join(", ")),
+            Some(error.abi_signature()),
+        ));
+    }
+
+    None
+}
+
+/// Panic codes inserted by the Solidity compiler.
+fn panic_description(code: u64) -> &'static str {
+    match code {
+        0x00 => "generic compiler panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic underflow or overflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum conversion",
+        0x22 => "invalid storage byte array encoding",
+        0x31 => "pop on empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "too much memory allocated",
+        0x51 => "call to zero-initialized internal function",
+        _ => "unknown panic code",
+    }
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
 pub struct DatabaseContractInformation {
     pub chain: String,
     pub contract: String,
     pub abi: Option<String>,
     pub name: Option<String>,
     pub verified: bool,
+    pub implementation: Option<String>,
+    /// The ABI was inferred from the selectors and event topics found in the bytecode.
+    pub inferred: bool,
+}
+
+/// Implementation used by a proxy contract from the block it was detected at.
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseContractImplementation {
+    pub block: i64,
+    pub chain: String,
+    pub contract: String,
+    pub implementation: String,
+    pub standard: String,
+}
+
+/// Verified source code of a contract, `files` is a JSONB object with the content of each
+/// source file by its path.
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseContractSource {
+    pub chain: String,
+    pub compiler_version: Option<String>,
+    pub constructor_arguments: Option<String>,
+    pub contract: String,
+    pub decoded_constructor_arguments: Option<String>,
+    pub evm_version: Option<String>,
+    pub files: String,
+    pub license_type: Option<String>,
+    pub optimization_used: Option<bool>,
+    pub runs: Option<i64>,
+    pub source: String,
+}
+
+impl DatabaseContractSource {
+    pub fn from_source_code(
+        chain: &str,
+        contract: &str,
+        abi: &Option<String>,
+        source: &str,
+        source_code: &SourceCode,
+    ) -> Self {
+        let decoded_constructor_arguments = match (abi, &source_code.constructor_arguments) {
+            (Some(abi), Some(arguments)) => decode_constructor_arguments(abi, arguments),
+            _ => None,
+        };
+
+        Self {
+            chain: chain.to_owned(),
+            compiler_version: source_code.compiler_version.clone(),
+            constructor_arguments: source_code.constructor_arguments.clone(),
+            contract: contract.to_owned(),
+            decoded_constructor_arguments,
+            evm_version: source_code.evm_version.clone(),
+            files: serde_json::to_string(&source_code.files).unwrap(),
+            license_type: source_code.license_type.clone(),
+            optimization_used: source_code.optimization_used,
+            runs: source_code.runs,
+            source: source.to_owned(),
+        }
+    }
+}
+
+/// Decodes the arguments appended to the creation code with the constructor inputs of the ABI,
+/// unnamed inputs are stored by their position.
+fn decode_constructor_arguments(abi: &str, arguments: &str) -> Option<String> {
+    let contract: ethabi::Contract = serde_json::from_str(abi).ok()?;
+
+    let constructor = contract.constructor?;
+
+    let data = hex::decode(arguments.trim_start_matches("0x")).ok()?;
+
+    let param_types: Vec<ParamType> = constructor
+        .inputs
+        .iter()
+        .map(|input| input.kind.clone())
+        .collect();
+
+    let tokens = ethabi::decode(&param_types, &data).ok()?;
+
+    let mut params = serde_json::Map::new();
+
+    for (i, (input, token)) in constructor.inputs.iter().zip(tokens.iter()).enumerate() {
+        let name = if input.name.is_empty() {
+            format!("arg{}", i)
+        } else {
+            input.name.clone()
+        };
+
+        params.insert(name, format_token(token));
+    }
+
+    Some(serde_json::Value::Object(params).to_string())
+}
+
+/// Activity of an address in a chain, the nonce is the next one expected from the address.
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseAddress {
+    pub address: String,
+    pub chain: String,
+    pub contract: bool,
+    pub first_seen_block: i64,
+    pub last_seen_block: i64,
+    pub nonce: i64,
+    pub tx_in: i64,
+    pub tx_out: i64,
+}
+
+impl DatabaseAddress {
+    pub fn new(address: &str, chain: &str, block: i64) -> Self {
+        Self {
+            address: address.to_owned(),
+            chain: chain.to_owned(),
+            contract: false,
+            first_seen_block: block,
+            last_seen_block: block,
+            nonce: 0,
+            tx_in: 0,
+            tx_out: 0,
+        }
+    }
+
+    fn seen_at(&mut self, block: i64) {
+        self.first_seen_block = min(self.first_seen_block, block);
+        self.last_seen_block = max(self.last_seen_block, block);
+    }
+
+    /// Aggregates the activity of the addresses sending, receiving or created by the
+    /// transactions of a batch of blocks.
+    pub fn from_batch(
+        transactions: &[DatabaseTransaction],
+        contracts: &[DatabaseContract],
+    ) -> Vec<Self> {
+        let mut addresses: HashMap<(String, String), Self> = HashMap::new();
+
+        let zero_address = format_address(H160::zero());
+
+        for transaction in transactions {
+            let sender = addresses
+                .entry((transaction.from_address.clone(), transaction.chain.clone()))
+                .or_insert_with(|| {
+                    Self::new(
+                        &transaction.from_address,
+                        &transaction.chain,
+                        transaction.block_number,
+                    )
+                });
+
+            sender.seen_at(transaction.block_number);
+            sender.tx_out += 1;
+
+            if let Ok(nonce) = transaction.nonce.parse::<i64>() {
+                sender.nonce = max(sender.nonce, nonce + 1);
+            }
+
+            // Contract creations are sent to the zero address.
+            if transaction.to_address == zero_address {
+                continue;
+            }
+
+            let receiver = addresses
+                .entry((transaction.to_address.clone(), transaction.chain.clone()))
+                .or_insert_with(|| {
+                    Self::new(
+                        &transaction.to_address,
+                        &transaction.chain,
+                        transaction.block_number,
+                    )
+                });
+
+            receiver.seen_at(transaction.block_number);
+            receiver.tx_in += 1;
+        }
+
+        for contract in contracts {
+            let created = addresses
+                .entry((contract.contract.clone(), contract.chain.clone()))
+                .or_insert_with(|| Self::new(&contract.contract, &contract.chain, contract.block));
+
+            created.seen_at(contract.block);
+            created.contract = true;
+        }
+
+        addresses.into_values().collect()
+    }
+}
+
+pub const ACTIVITY_KIND_TRANSACTION_IN: &str = "transaction-in";
+pub const ACTIVITY_KIND_TRANSACTION_OUT: &str = "transaction-out";
+pub const ACTIVITY_KIND_ERC20_TRANSFER_IN: &str = "erc20-transfer-in";
+pub const ACTIVITY_KIND_ERC20_TRANSFER_OUT: &str = "erc20-transfer-out";
+pub const ACTIVITY_KIND_LOG: &str = "log";
+
+/// Entry of the activity feed of an address. Transaction entries have a `log_index` of -1 so
+/// they come after the entries of their logs in descending order.
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseAddressActivity {
+    pub address: String,
+    pub amount: Option<String>,
+    pub block_number: i64,
+    pub chain: String,
+    pub counterparty: Option<String>,
+    pub hash: String,
+    pub kind: String,
+    pub log_index: i64,
+    pub token: Option<String>,
+}
+
+impl DatabaseAddressActivity {
+    /// Entries of the sender and the receiver of a transaction, contract creations only have
+    /// the sender entry.
+    pub fn from_transaction(transaction: &DatabaseTransaction) -> Vec<Self> {
+        let mut activity = vec![Self {
+            address: transaction.from_address.clone(),
+            amount: Some(transaction.value.clone()),
+            block_number: transaction.block_number,
+            chain: transaction.chain.clone(),
+            counterparty: Some(transaction.to_address.clone()),
+            hash: transaction.hash.clone(),
+            kind: ACTIVITY_KIND_TRANSACTION_OUT.to_owned(),
+            log_index: -1,
+            token: None,
+        }];
+
+        if transaction.to_address != format_address(H160::zero()) {
+            activity.push(Self {
+                address: transaction.to_address.clone(),
+                amount: Some(transaction.value.clone()),
+                block_number: transaction.block_number,
+                chain: transaction.chain.clone(),
+                counterparty: Some(transaction.from_address.clone()),
+                hash: transaction.hash.clone(),
+                kind: ACTIVITY_KIND_TRANSACTION_IN.to_owned(),
+                log_index: -1,
+                token: None,
+            });
+        }
+
+        activity
+    }
+
+    /// Entry of the contract that emitted a log, with the sender of the transaction as the
+    /// counterparty.
+    pub fn from_log(log: &DatabaseLog, block_number: i64, sender: &str) -> Self {
+        Self {
+            address: log.address.clone(),
+            amount: None,
+            block_number,
+            chain: log.chain.clone(),
+            counterparty: Some(sender.to_owned()),
+            hash: log.hash.clone(),
+            kind: ACTIVITY_KIND_LOG.to_owned(),
+            log_index: log.log_index,
+            token: None,
+        }
+    }
+}
+
+/// Position in the activity feed of an address, the next page starts after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityCursor {
+    pub block_number: i64,
+    pub hash: String,
+    pub log_index: i64,
+    pub kind: String,
+}
+
+impl ActivityCursor {
+    pub fn from_activity(activity: &DatabaseAddressActivity) -> Self {
+        Self {
+            block_number: activity.block_number,
+            hash: activity.hash.clone(),
+            log_index: activity.log_index,
+            kind: activity.kind.clone(),
+        }
+    }
+
+    /// Opaque representation of the cursor to hand over to clients.
+    pub fn encode(&self) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            self.block_number, self.hash, self.log_index, self.kind
+        )
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let mut parts = cursor.splitn(4, ':');
+
+        Some(Self {
+            block_number: parts.next()?.parse().ok()?,
+            hash: parts.next()?.to_owned(),
+            log_index: parts.next()?.parse().ok()?,
+            kind: parts.next()?.to_owned(),
+        })
+    }
+}
+
+/// Adapter that handles the positions held in a contract.
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseContractAdapter {
+    pub adapter_id: String,
+    pub address: String,
+    pub chain: String,
 }
 
 #[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
@@ -1,3 +1,5 @@
+pub mod abi_sources;
+pub mod adapters;
 pub mod chains;
 pub mod configs;
 pub mod db;
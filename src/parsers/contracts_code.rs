@@ -0,0 +1,641 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    chains::chains::get_chain,
+    db::{
+        db::{get_chunks, Database},
+        models::models::DatabaseContract,
+    },
+    rpc::{
+        multicall::{aggregate3, is_execution_error, Call},
+        proxies::get_slot,
+    },
+    utils::{format_bytes, format_hash},
+};
+use anyhow::Result;
+use ethabi::{Address, ParamType, Token};
+use ethers::{
+    providers::{Http, Middleware, Provider},
+    types::H256,
+    utils::{id, keccak256},
+};
+use field_count::FieldCount;
+use futures::future::join_all;
+use log::info;
+use rand::seq::SliceRandom;
+use sqlx::QueryBuilder;
+
+pub const CONTRACT_STANDARD_ERC20: &str = "erc20";
+pub const CONTRACT_STANDARD_ERC165: &str = "erc165";
+pub const CONTRACT_STANDARD_ERC721: &str = "erc721";
+pub const CONTRACT_STANDARD_ERC1155: &str = "erc1155";
+pub const CONTRACT_STANDARD_PROXY: &str = "proxy";
+pub const CONTRACT_STANDARD_MULTISIG: &str = "multisig";
+
+/// Amount of `eth_getCode` requests sent concurrently to a single RPC.
+pub const CODE_REQUESTS_BATCH_SIZE: usize = 50;
+
+/// Attempts returning an empty code before a contract is considered self destructed, or
+/// failing the `supportsInterface` calls before it is classified from its bytecode only.
+pub const MAX_CODE_ATTEMPTS: i64 = 5;
+
+pub const ERC165_INTERFACE_ID: [u8; 4] = [0x01, 0xff, 0xc9, 0xa7];
+pub const ERC721_INTERFACE_ID: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+pub const ERC1155_INTERFACE_ID: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
+/// Prefix of the EIP-1167 minimal proxies, followed by the implementation address.
+pub const EIP1167_PREFIX: [u8; 10] = [0x36, 0x3d, 0x3d, 0x37, 0x3d, 0x3d, 0x3d, 0x36, 0x3d, 0x73];
+
+pub const ERC20_METHODS: &[&str] = &[
+    "totalSupply()",
+    "balanceOf(address)",
+    "transfer(address,uint256)",
+    "transferFrom(address,address,uint256)",
+    "approve(address,uint256)",
+    "allowance(address,address)",
+];
+
+pub const ERC721_METHODS: &[&str] = &[
+    "ownerOf(uint256)",
+    "safeTransferFrom(address,address,uint256)",
+    "setApprovalForAll(address,bool)",
+];
+
+pub const ERC1155_METHODS: &[&str] = &[
+    "balanceOfBatch(address[],uint256[])",
+    "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+];
+
+pub const MULTISIG_METHODS: &[&str] = &[
+    "getOwners()",
+    "getThreshold()",
+    "execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)",
+];
+
+/// Runtime bytecode shared by all the contracts with the same code hash.
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseContractBytecode {
+    pub bytecode: String,
+    pub code_hash: String,
+    pub size: i64,
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseContractCode {
+    pub chain: String,
+    pub code_hash: Option<String>,
+    pub contract: String,
+    pub standards: Vec<String>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DatabaseContractWithAttempts {
+    #[sqlx(flatten)]
+    pub contract: DatabaseContract,
+    pub code_attempts: i64,
+}
+
+pub struct ContractsCode {}
+
+impl ContractsCode {
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseContractWithAttempts>> {
+        let connection = db.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseContractWithAttempts>(
+            "SELECT block, chain, contract, creator, hash, parsed, verified, code_attempts FROM contracts WHERE code_parsed = false AND (code_next_attempt_at IS NULL OR code_next_attempt_at < now()) LIMIT 500",
+        )
+        .fetch_all(connection)
+        .await;
+
+        match rows {
+            Ok(contracts) => Ok(contracts),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Stores the runtime bytecode of the contracts deduplicated by its hash and the standards
+    /// implemented by each contract. Contracts without a response, created after the latest
+    /// block of the RPC, still without code or whose interfaces calls failed are fetched again
+    /// later, up to `MAX_CODE_ATTEMPTS`.
+    pub async fn parse(
+        &self,
+        db: &Database,
+        contracts: &[DatabaseContractWithAttempts],
+    ) -> Result<()> {
+        let mut contracts_by_chain: HashMap<String, Vec<&DatabaseContractWithAttempts>> =
+            HashMap::new();
+
+        for contract in contracts {
+            contracts_by_chain
+                .entry(contract.contract.chain.clone())
+                .or_default()
+                .push(contract);
+        }
+
+        let mut db_bytecodes: HashMap<String, DatabaseContractBytecode> = HashMap::new();
+
+        let mut db_contracts_code: Vec<DatabaseContractCode> = Vec::new();
+
+        let mut retry_contracts: Vec<(String, String)> = Vec::new();
+
+        for (chain, chain_contracts) in contracts_by_chain {
+            let retry = |contracts: &[&DatabaseContractWithAttempts]| {
+                contracts
+                    .iter()
+                    .map(|contract| (contract.contract.contract.clone(), chain.clone()))
+                    .collect::<Vec<(String, String)>>()
+            };
+
+            let rpcs = get_chain(chain.clone()).get_rpcs();
+
+            let rpc = rpcs.choose(&mut rand::thread_rng()).unwrap();
+
+            let provider = match Provider::<Http>::try_from(rpc.as_str()) {
+                Ok(provider) => provider,
+                Err(_) => {
+                    retry_contracts.extend(retry(&chain_contracts));
+                    continue;
+                }
+            };
+
+            // The code is read at the latest block of the RPC, which must include the block
+            // where the contract was created.
+            let latest_block = match provider.get_block_number().await {
+                Ok(block) => block.as_u64() as i64,
+                Err(_) => {
+                    retry_contracts.extend(retry(&chain_contracts));
+                    continue;
+                }
+            };
+
+            let (chain_contracts, pending): (Vec<_>, Vec<_>) = chain_contracts
+                .into_iter()
+                .partition(|contract| contract.contract.block <= latest_block);
+
+            retry_contracts.extend(retry(&pending));
+
+            for batch in chain_contracts.chunks(CODE_REQUESTS_BATCH_SIZE) {
+                let codes = join_all(
+                    batch
+                        .iter()
+                        .map(|contract| self.get_code(&provider, &contract.contract.contract)),
+                )
+                .await;
+
+                let mut fetched: Vec<(&DatabaseContractWithAttempts, Vec<u8>)> = Vec::new();
+
+                for (contract, code) in batch.iter().zip(codes) {
+                    let address = &contract.contract.contract;
+
+                    match code {
+                        Some(code) if !code.is_empty() => fetched.push((contract, code)),
+                        // Self destructed contracts have no code, but neither do contracts
+                        // created in a block the RPC has not processed yet.
+                        Some(_) if contract.code_attempts + 1 >= MAX_CODE_ATTEMPTS => {
+                            db_contracts_code.push(DatabaseContractCode {
+                                chain: chain.clone(),
+                                code_hash: None,
+                                contract: address.clone(),
+                                standards: Vec::new(),
+                            })
+                        }
+                        _ => retry_contracts.push((address.clone(), chain.clone())),
+                    }
+                }
+
+                let addresses: Vec<&String> = fetched
+                    .iter()
+                    .map(|(contract, _)| &contract.contract.contract)
+                    .collect();
+
+                let interfaces = self.get_batch_interfaces(&provider, &addresses).await;
+
+                for ((contract, code), interfaces) in fetched.into_iter().zip(interfaces) {
+                    let address = &contract.contract.contract;
+
+                    let interfaces = match interfaces {
+                        Some(interfaces) => interfaces,
+                        None if contract.code_attempts + 1 >= MAX_CODE_ATTEMPTS => HashSet::new(),
+                        None => {
+                            retry_contracts.push((address.clone(), chain.clone()));
+                            continue;
+                        }
+                    };
+
+                    let code_hash = format_hash(H256::from(keccak256(&code)));
+
+                    db_bytecodes.entry(code_hash.clone()).or_insert_with(|| {
+                        DatabaseContractBytecode {
+                            bytecode: format_bytes(&code.clone().into()),
+                            code_hash: code_hash.clone(),
+                            size: code.len() as i64,
+                        }
+                    });
+
+                    db_contracts_code.push(DatabaseContractCode {
+                        chain: chain.clone(),
+                        code_hash: Some(code_hash),
+                        contract: address.clone(),
+                        standards: get_standards(&code, &interfaces),
+                    });
+                }
+            }
+        }
+
+        let db_bytecodes: Vec<DatabaseContractBytecode> = db_bytecodes.into_values().collect();
+
+        if !db_bytecodes.is_empty() {
+            self.store_bytecodes(db, &db_bytecodes).await?;
+        }
+
+        if !db_contracts_code.is_empty() {
+            self.update_contracts_code(db, &db_contracts_code).await?;
+        }
+
+        if !retry_contracts.is_empty() {
+            self.update_contracts_code_attempts(db, &retry_contracts)
+                .await?;
+        }
+
+        info!(
+            "ContractsCode: Classified {} contracts with {} unique bytecodes, {} scheduled to retry.",
+            db_contracts_code.len(),
+            db_bytecodes.len(),
+            retry_contracts.len()
+        );
+
+        Ok(())
+    }
+
+    async fn get_code(&self, provider: &Provider<Http>, address: &str) -> Option<Vec<u8>> {
+        let address = address.parse::<Address>().ok()?;
+
+        match provider.get_code(address, None).await {
+            Ok(code) => Some(code.to_vec()),
+            Err(_) => None,
+        }
+    }
+
+    /// Returns the interfaces of each contract, `None` for the contracts whose calls failed.
+    /// When the `aggregate3` call reverts, e.g. a contract consuming all the gas, the batch is
+    /// split in halves until the failing contracts are isolated. When the RPC fails, the
+    /// remaining contracts are left without interfaces at once.
+    async fn get_batch_interfaces(
+        &self,
+        provider: &Provider<Http>,
+        contracts: &[&String],
+    ) -> Vec<Option<HashSet<[u8; 4]>>> {
+        let mut interfaces: Vec<Option<HashSet<[u8; 4]>>> = vec![None; contracts.len()];
+
+        let mut pending: Vec<(usize, &[&String])> = vec![(0, contracts)];
+
+        while let Some((offset, batch)) = pending.pop() {
+            match self.get_interfaces(provider, batch).await {
+                Ok(batch_interfaces) => {
+                    for (i, batch_interfaces) in batch_interfaces.into_iter().enumerate() {
+                        interfaces[offset + i] = Some(batch_interfaces);
+                    }
+                }
+                Err(err) if is_execution_error(&err) && batch.len() > 1 => {
+                    let (left, right) = batch.split_at(batch.len() / 2);
+
+                    pending.push((offset, left));
+                    pending.push((offset + left.len(), right));
+                }
+                Err(err) if is_execution_error(&err) => {}
+                Err(_) => break,
+            }
+        }
+
+        interfaces
+    }
+
+    /// Calls `supportsInterface` for the ERC165, ERC721 and ERC1155 interfaces, and for the
+    /// invalid `0xffffffff` interface that a compliant contract must reject.
+    async fn get_interfaces(
+        &self,
+        provider: &Provider<Http>,
+        contracts: &[&String],
+    ) -> Result<Vec<HashSet<[u8; 4]>>> {
+        if contracts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let interfaces = [
+            ERC165_INTERFACE_ID,
+            [0xff, 0xff, 0xff, 0xff],
+            ERC721_INTERFACE_ID,
+            ERC1155_INTERFACE_ID,
+        ];
+
+        let mut calls: Vec<Call> = Vec::new();
+
+        for address in contracts {
+            let target = address.parse::<Address>().unwrap_or_default();
+
+            for interface in interfaces {
+                let mut data = id("supportsInterface(bytes4)").to_vec();
+
+                data.extend(ethabi::encode(&[Token::FixedBytes(interface.to_vec())]));
+
+                calls.push(Call { target, data });
+            }
+        }
+
+        let results = aggregate3(provider, &calls).await?;
+
+        let supported_interfaces = results
+            .chunks(interfaces.len())
+            .map(|results| {
+                let supported: Vec<bool> = results
+                    .iter()
+                    .map(|result| result.as_deref().map(decode_bool).unwrap_or(false))
+                    .collect();
+
+                if !supported[0] || supported[1] {
+                    return HashSet::new();
+                }
+
+                interfaces
+                    .iter()
+                    .zip(supported)
+                    .filter(|(interface, supported)| {
+                        *supported && **interface != [0xff, 0xff, 0xff, 0xff]
+                    })
+                    .map(|(interface, _)| *interface)
+                    .collect()
+            })
+            .collect();
+
+        Ok(supported_interfaces)
+    }
+
+    async fn store_bytecodes(
+        &self,
+        db: &Database,
+        bytecodes: &[DatabaseContractBytecode],
+    ) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(bytecodes.len(), DatabaseContractBytecode::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder =
+                QueryBuilder::new("UPSERT INTO contracts_bytecode (bytecode, code_hash, size) ");
+
+            query_builder.push_values(&bytecodes[start..end], |mut row, bytecode| {
+                row.push_bind(bytecode.bytecode.clone())
+                    .push_bind(bytecode.code_hash.clone())
+                    .push_bind(bytecode.size);
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store contracts bytecode into database");
+        }
+
+        Ok(())
+    }
+
+    async fn update_contracts_code_attempts(
+        &self,
+        db: &Database,
+        contracts: &[(String, String)],
+    ) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(contracts.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPDATE contracts SET code_attempts = code_attempts + 1, code_next_attempt_at = now() + INTERVAL '1 minute' * power(2, least(code_attempts, 10))::INT WHERE (contract, chain) IN ",
+            );
+
+            query_builder.push_tuples(&contracts[start..end], |mut row, (contract, chain)| {
+                row.push_bind(contract.clone()).push_bind(chain.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to update contracts code attempts into database");
+        }
+
+        Ok(())
+    }
+
+    async fn update_contracts_code(
+        &self,
+        db: &Database,
+        contracts: &[DatabaseContractCode],
+    ) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(contracts.len(), DatabaseContractCode::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPDATE contracts SET code_hash = data.code_hash, code_parsed = true, standards = data.standards FROM (",
+            );
+
+            query_builder.push_values(&contracts[start..end], |mut row, contract| {
+                row.push_bind(contract.chain.clone())
+                    .push_unseparated("::TEXT")
+                    .push_bind(contract.code_hash.clone())
+                    .push_unseparated("::TEXT")
+                    .push_bind(contract.contract.clone())
+                    .push_unseparated("::TEXT")
+                    .push_bind(contract.standards.clone())
+                    .push_unseparated("::TEXT[]");
+            });
+
+            query_builder.push(
+                ") AS data (chain, code_hash, contract, standards) WHERE contracts.contract = data.contract AND contracts.chain = data.chain",
+            );
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to update contracts code into database");
+        }
+
+        Ok(())
+    }
+}
+
+/// Classifies a contract with the interfaces reported through ERC165 and the selectors of the
+/// standard methods found in its bytecode.
+pub fn get_standards(code: &[u8], interfaces: &HashSet<[u8; 4]>) -> Vec<String> {
+    let selectors = get_selectors(code);
+
+    let has_methods =
+        |methods: &[&str]| methods.iter().all(|method| selectors.contains(&id(method)));
+
+    let mut standards: Vec<&str> = Vec::new();
+
+    if !interfaces.is_empty() {
+        standards.push(CONTRACT_STANDARD_ERC165);
+    }
+
+    let erc721 = interfaces.contains(&ERC721_INTERFACE_ID) || has_methods(ERC721_METHODS);
+
+    if erc721 {
+        standards.push(CONTRACT_STANDARD_ERC721);
+    } else if has_methods(ERC20_METHODS) {
+        standards.push(CONTRACT_STANDARD_ERC20);
+    }
+
+    if interfaces.contains(&ERC1155_INTERFACE_ID) || has_methods(ERC1155_METHODS) {
+        standards.push(CONTRACT_STANDARD_ERC1155);
+    }
+
+    if is_proxy(code) {
+        standards.push(CONTRACT_STANDARD_PROXY);
+    }
+
+    if has_methods(MULTISIG_METHODS) {
+        standards.push(CONTRACT_STANDARD_MULTISIG);
+    }
+
+    standards
+        .into_iter()
+        .map(|standard| standard.to_owned())
+        .collect()
+}
+
+/// Returns the selectors compared by the function dispatcher, the value of a `PUSH4` followed
+/// by `EQ`, a `PUSHn` with the function offset and `JUMPI`, with `DUP` and `SWAP` instructions
+/// allowed in between. The compiler pushes selectors starting with a zero byte with `PUSH3`.
+/// Other `PUSH4` values like the selectors of external calls or interface ids are ignored.
+/// Push data is skipped while walking the code so it is never read as instructions.
+pub fn get_selectors(code: &[u8]) -> HashSet<[u8; 4]> {
+    let mut instructions: Vec<(u8, &[u8])> = Vec::new();
+
+    let mut i = 0;
+
+    while i < code.len() {
+        let opcode = code[i];
+
+        // PUSH1 to PUSH32
+        let size = if (0x60..=0x7f).contains(&opcode) {
+            (opcode - 0x5f) as usize
+        } else {
+            0
+        };
+
+        let end = (i + 1 + size).min(code.len());
+
+        instructions.push((opcode, &code[i + 1..end]));
+
+        i += size + 1;
+    }
+
+    let mut selectors = HashSet::new();
+
+    for (position, (opcode, data)) in instructions.iter().enumerate() {
+        // PUSH3 and PUSH4
+        if !(*opcode == 0x62 && data.len() == 3 || *opcode == 0x63 && data.len() == 4) {
+            continue;
+        }
+
+        // DUP1 to DUP16 and SWAP1 to SWAP16
+        let mut next = instructions[position + 1..]
+            .iter()
+            .map(|(opcode, _)| *opcode)
+            .skip_while(|opcode| (0x80..=0x9f).contains(opcode));
+
+        let is_dispatcher = next.next() == Some(0x14)
+            && matches!(next.next(), Some(0x60..=0x7f))
+            && next.next() == Some(0x57);
+
+        if is_dispatcher {
+            let mut selector = [0u8; 4];
+
+            selector[4 - data.len()..].copy_from_slice(data);
+
+            selectors.insert(selector);
+        }
+    }
+
+    selectors
+}
+
+/// Instructions walked back from a `LOG1` to `LOG4` looking for the `PUSH32` of its topic, the
+/// event arguments are encoded in between.
+pub const LOG_TOPIC_DISTANCE: usize = 128;
+
+/// Returns the 32 bytes pushed by the `PUSH32` instructions preceding a `LOG1` to `LOG4`, which
+/// include the topic of the events emitted by the contract.
+pub fn get_event_topics(code: &[u8]) -> HashSet<[u8; 32]> {
+    let mut topics = HashSet::new();
+
+    // Values pushed with the index of the instruction that pushed them.
+    let mut pushed: Vec<(usize, [u8; 32])> = Vec::new();
+
+    let mut instruction = 0;
+
+    let mut i = 0;
+
+    while i < code.len() {
+        let opcode = code[i];
+
+        if (0x60..=0x7f).contains(&opcode) {
+            let size = (opcode - 0x5f) as usize;
+
+            if opcode == 0x7f && i + 32 < code.len() {
+                let mut topic = [0u8; 32];
+
+                topic.copy_from_slice(&code[i + 1..i + 33]);
+
+                pushed.push((instruction, topic));
+            }
+
+            i += size;
+        } else if (0xa1..=0xa4).contains(&opcode) {
+            for (index, topic) in &pushed {
+                if instruction - index <= LOG_TOPIC_DISTANCE {
+                    topics.insert(*topic);
+                }
+            }
+        }
+
+        instruction += 1;
+
+        i += 1;
+    }
+
+    topics
+}
+
+/// Minimal proxies are matched by their prefix, other proxies by the storage slots of the
+/// standards that keep the implementation address in storage.
+pub fn is_proxy(code: &[u8]) -> bool {
+    if code.starts_with(&EIP1167_PREFIX) {
+        return true;
+    }
+
+    let slots = [
+        get_slot("eip1967.proxy.implementation", true),
+        get_slot("eip1967.proxy.beacon", true),
+        get_slot("PROXIABLE", false),
+        get_slot("org.zeppelinos.proxy.implementation", false),
+    ];
+
+    slots
+        .iter()
+        .any(|slot| code.windows(32).any(|window| window == slot.as_bytes()))
+}
+
+fn decode_bool(data: &[u8]) -> bool {
+    match ethabi::decode(&[ParamType::Bool], data) {
+        Ok(tokens) => matches!(tokens.first(), Some(Token::Bool(true))),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    db::{
+        db::{get_chunks, Database},
+        models::models::{DatabaseLog, DatabaseLogWithBlock},
+    },
+    utils::format_token,
+};
+use anyhow::Result;
+use ethabi::{Contract, Event};
+use ethers::abi::{parse_abi, EventExt};
+use field_count::FieldCount;
+use log::info;
+use serde_json::{Map, Value};
+use sqlx::QueryBuilder;
+
+/// Well known events used to decode logs emitted by contracts without a verified ABI.
+pub const KNOWN_EVENTS: &[&str] = &[
+    "event Transfer(address indexed from, address indexed to, uint256 value)",
+    "event Transfer(address indexed from, address indexed to, uint256 indexed tokenId)",
+    "event Approval(address indexed owner, address indexed spender, uint256 value)",
+    "event Approval(address indexed owner, address indexed approved, uint256 indexed tokenId)",
+    "event ApprovalForAll(address indexed owner, address indexed operator, bool approved)",
+    "event TransferSingle(address indexed operator, address indexed from, address indexed to, uint256 id, uint256 value)",
+    "event TransferBatch(address indexed operator, address indexed from, address indexed to, uint256[] ids, uint256[] values)",
+    "event Deposit(address indexed dst, uint256 wad)",
+    "event Withdrawal(address indexed src, uint256 wad)",
+    "event OwnershipTransferred(address indexed previousOwner, address indexed newOwner)",
+    "event Upgraded(address indexed implementation)",
+];
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseDecodedLog {
+    pub address: String,
+    pub block_number: i64,
+    pub chain: String,
+    pub event: String,
+    pub hash: String,
+    pub log_index: i64,
+    pub params: String,
+    pub signature: String,
+    pub source: String,
+    pub topic: String,
+}
+
+pub struct DecodedLogs {}
+
+impl DecodedLogs {
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseLogWithBlock>> {
+        let connection = db.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseLogWithBlock>(
+            "SELECT logs.*, transactions.block_number FROM logs INNER JOIN transactions ON logs.hash = transactions.hash WHERE logs.decoded_logs_parsed = false LIMIT 500",
+        )
+        .fetch_all(connection)
+        .await;
+
+        match rows {
+            Ok(logs) => Ok(logs),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn parse(&self, db: &Database, logs: &Vec<DatabaseLogWithBlock>) -> Result<()> {
+        let contracts: Vec<(String, String)> = logs
+            .iter()
+            .map(|log| (log.log.address.clone(), log.log.chain.clone()))
+            .collect::<HashSet<(String, String)>>()
+            .into_iter()
+            .collect();
+
+        let mut abis: HashMap<(String, String), (Contract, &'static str)> = HashMap::new();
+
+        for contract_information in db.get_contracts_information(&contracts).await? {
+            let contract: Contract = match &contract_information.abi {
+                Some(abi) => match serde_json::from_str(abi) {
+                    Ok(contract) => contract,
+                    Err(_) => continue,
+                },
+                None => continue,
+            };
+
+            let source = if contract_information.inferred {
+                "inferred"
+            } else {
+                "abi"
+            };
+
+            abis.insert(
+                (contract_information.contract, contract_information.chain),
+                (contract, source),
+            );
+        }
+
+        let topics: Vec<String> = logs
+            .iter()
+            .filter_map(|log| log.log.topics.first().cloned().flatten())
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect();
+
+        // Events registry collected from every fetched ABI, used for contracts without one.
+        let mut registry_events: HashMap<String, Vec<Event>> = HashMap::new();
+
+        for db_event in db.get_events(&topics).await? {
+            if let Ok(event) = serde_json::from_str::<Event>(&db_event.abi) {
+                registry_events
+                    .entry(db_event.topic)
+                    .or_default()
+                    .push(event);
+            }
+        }
+
+        let known_events = self.get_known_events();
+
+        let mut db_decoded_logs: Vec<DatabaseDecodedLog> = Vec::new();
+
+        let mut db_parsed_logs: Vec<DatabaseLog> = Vec::new();
+
+        for log_with_block in logs {
+            let log = &log_with_block.log;
+
+            db_parsed_logs.push(log.to_owned());
+
+            let topic = match log.topics.first() {
+                Some(Some(topic)) => topic.clone(),
+                _ => continue,
+            };
+
+            let (contract_events, contract_source): (Vec<&Event>, &'static str) =
+                match abis.get(&(log.address.clone(), log.chain.clone())) {
+                    Some((contract, source)) => (
+                        contract
+                            .events()
+                            .filter(|event| {
+                                !event.anonymous && format!("{:?}", event.signature()) == topic
+                            })
+                            .collect(),
+                        source,
+                    ),
+                    None => (Vec::new(), "abi"),
+                };
+
+            let registry: Vec<&Event> = match registry_events.get(&topic) {
+                Some(events) => events.iter().collect(),
+                None => Vec::new(),
+            };
+
+            let known: Vec<&Event> = match known_events.get(&topic) {
+                Some(events) => events.iter().collect(),
+                None => Vec::new(),
+            };
+
+            let candidates = contract_events
+                .into_iter()
+                .map(|event| (event, contract_source))
+                .chain(registry.into_iter().map(|event| (event, "signature")))
+                .chain(known.into_iter().map(|event| (event, "signature")));
+
+            let raw_log = match log.to_raw_log() {
+                Some(raw_log) => raw_log,
+                None => continue,
+            };
+
+            for (event, source) in candidates {
+                let decoded = match event.parse_log(raw_log.clone()) {
+                    Ok(decoded) => decoded,
+                    Err(_) => continue,
+                };
+
+                let mut params = Map::new();
+
+                for (i, param) in decoded.params.into_iter().enumerate() {
+                    let name = if param.name.is_empty() {
+                        format!("arg{}", i)
+                    } else {
+                        param.name
+                    };
+
+                    params.insert(name, format_token(&param.value));
+                }
+
+                db_decoded_logs.push(DatabaseDecodedLog {
+                    address: log.address.clone(),
+                    block_number: log_with_block.block_number,
+                    chain: log.chain.clone(),
+                    event: event.name.clone(),
+                    hash: log.hash.clone(),
+                    log_index: log.log_index,
+                    params: Value::Object(params).to_string(),
+                    signature: event.abi_signature(),
+                    source: source.to_owned(),
+                    topic: topic.clone(),
+                });
+
+                break;
+            }
+        }
+
+        let connection = db.get_connection();
+
+        if !db_decoded_logs.is_empty() {
+            let chunks = get_chunks(db_decoded_logs.len(), DatabaseDecodedLog::field_count());
+
+            for (start, end) in chunks {
+                let mut query_builder = QueryBuilder::new(
+                    "UPSERT INTO decoded_logs (address, block_number, chain, event, hash, log_index, params, signature, source, topic) ",
+                );
+
+                query_builder.push_values(&db_decoded_logs[start..end], |mut row, decoded_log| {
+                    row.push_bind(decoded_log.address.clone())
+                        .push_bind(decoded_log.block_number)
+                        .push_bind(decoded_log.chain.clone())
+                        .push_bind(decoded_log.event.clone())
+                        .push_bind(decoded_log.hash.clone())
+                        .push_bind(decoded_log.log_index)
+                        .push_bind(decoded_log.params.clone())
+                        .push_unseparated("::JSONB")
+                        .push_bind(decoded_log.signature.clone())
+                        .push_bind(decoded_log.source.clone())
+                        .push_bind(decoded_log.topic.clone());
+                });
+
+                let query = query_builder.build();
+
+                query
+                    .execute(connection)
+                    .await
+                    .expect("Unable to store decoded logs into database");
+            }
+        }
+
+        info!(
+            "DecodedLogs: Inserted {} decoded logs from {} logs to the database.",
+            db_decoded_logs.len(),
+            db_parsed_logs.len()
+        );
+
+        if !db_parsed_logs.is_empty() {
+            db.update_logs_parsed("decoded_logs_parsed", &db_parsed_logs)
+                .await
+                .unwrap();
+        }
+
+        Ok(())
+    }
+
+    /// Returns the known events grouped by their topic, different events can share the same
+    /// topic when only the indexed parameters change (e.g. ERC20 and ERC721 `Transfer`).
+    pub fn get_known_events(&self) -> HashMap<String, Vec<Event>> {
+        let mut known_events: HashMap<String, Vec<Event>> = HashMap::new();
+
+        let abi = parse_abi(KNOWN_EVENTS).expect("Unable to parse known events");
+
+        for event in abi.events() {
+            known_events
+                .entry(format!("{:?}", event.signature()))
+                .or_default()
+                .push(event.clone());
+        }
+
+        known_events
+    }
+}
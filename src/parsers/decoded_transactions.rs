@@ -0,0 +1,244 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    db::{
+        db::{get_chunks, Database},
+        models::models::DatabaseTransaction,
+    },
+    utils::{format_token, parse_signature},
+};
+use anyhow::Result;
+use ethabi::{Contract, ParamType};
+use ethers::abi::FunctionExt;
+use field_count::FieldCount;
+use log::info;
+use serde_json::{Map, Value};
+use sqlx::QueryBuilder;
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseDecodedTransaction {
+    pub block_number: i64,
+    pub chain: String,
+    pub hash: String,
+    pub method: String,
+    pub name: String,
+    pub params: String,
+    pub signature: String,
+    pub source: String,
+    pub to_address: String,
+}
+
+pub struct DecodedTransactions {}
+
+impl DecodedTransactions {
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseTransaction>> {
+        let connection = db.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseTransaction>(
+            "SELECT * FROM transactions WHERE decoded_transactions_parsed = false LIMIT 500",
+        )
+        .fetch_all(connection)
+        .await;
+
+        match rows {
+            Ok(transactions) => Ok(transactions),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn parse(&self, db: &Database, transactions: &[DatabaseTransaction]) -> Result<()> {
+        // Plain value transfers and contract creations don't have a method to decode.
+        let calls: Vec<&DatabaseTransaction> = transactions
+            .iter()
+            .filter(|transaction| transaction.input.len() >= 10)
+            .collect();
+
+        let contracts: Vec<(String, String)> = calls
+            .iter()
+            .map(|transaction| (transaction.to_address.clone(), transaction.chain.clone()))
+            .collect::<HashSet<(String, String)>>()
+            .into_iter()
+            .collect();
+
+        let mut abis: HashMap<(String, String), (Contract, &'static str)> = HashMap::new();
+
+        for contract_information in db.get_contracts_information(&contracts).await? {
+            let contract: Contract = match &contract_information.abi {
+                Some(abi) => match serde_json::from_str(abi) {
+                    Ok(contract) => contract,
+                    Err(_) => continue,
+                },
+                None => continue,
+            };
+
+            let source = if contract_information.inferred {
+                "inferred"
+            } else {
+                "abi"
+            };
+
+            abis.insert(
+                (contract_information.contract, contract_information.chain),
+                (contract, source),
+            );
+        }
+
+        let methods: Vec<String> = calls
+            .iter()
+            .map(|transaction| transaction.method.clone())
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect();
+
+        let mut signatures: HashMap<String, String> = HashMap::new();
+
+        for method in db.get_methods(&methods).await? {
+            if let Some(signature) = method.signature {
+                signatures.insert(method.method, signature);
+            }
+        }
+
+        let mut db_decoded_transactions: Vec<DatabaseDecodedTransaction> = Vec::new();
+
+        for transaction in calls {
+            let input = match hex::decode(transaction.input.trim_start_matches("0x")) {
+                Ok(input) => input,
+                Err(_) => continue,
+            };
+
+            let decoded = abis
+                .get(&(transaction.to_address.clone(), transaction.chain.clone()))
+                .and_then(|(contract, source)| self.decode_with_abi(contract, source, &input))
+                .or_else(|| {
+                    signatures
+                        .get(&transaction.method)
+                        .and_then(|signature| self.decode_with_signature(signature, &input))
+                });
+
+            let (name, signature, params, source) = match decoded {
+                Some(decoded) => decoded,
+                None => continue,
+            };
+
+            db_decoded_transactions.push(DatabaseDecodedTransaction {
+                block_number: transaction.block_number,
+                chain: transaction.chain.clone(),
+                hash: transaction.hash.clone(),
+                method: transaction.method.clone(),
+                name,
+                params: params.to_string(),
+                signature,
+                source: source.to_owned(),
+                to_address: transaction.to_address.clone(),
+            });
+        }
+
+        let connection = db.get_connection();
+
+        if !db_decoded_transactions.is_empty() {
+            let chunks = get_chunks(
+                db_decoded_transactions.len(),
+                DatabaseDecodedTransaction::field_count(),
+            );
+
+            for (start, end) in chunks {
+                let mut query_builder = QueryBuilder::new(
+                    "UPSERT INTO decoded_transactions (block_number, chain, hash, method, name, params, signature, source, to_address) ",
+                );
+
+                query_builder.push_values(
+                    &db_decoded_transactions[start..end],
+                    |mut row, decoded_transaction| {
+                        row.push_bind(decoded_transaction.block_number)
+                            .push_bind(decoded_transaction.chain.clone())
+                            .push_bind(decoded_transaction.hash.clone())
+                            .push_bind(decoded_transaction.method.clone())
+                            .push_bind(decoded_transaction.name.clone())
+                            .push_bind(decoded_transaction.params.clone())
+                            .push_unseparated("::JSONB")
+                            .push_bind(decoded_transaction.signature.clone())
+                            .push_bind(decoded_transaction.source.clone())
+                            .push_bind(decoded_transaction.to_address.clone());
+                    },
+                );
+
+                let query = query_builder.build();
+
+                query
+                    .execute(connection)
+                    .await
+                    .expect("Unable to store decoded transactions into database");
+            }
+        }
+
+        info!(
+            "DecodedTransactions: Inserted {} decoded transactions from {} transactions to the database.",
+            db_decoded_transactions.len(),
+            transactions.len()
+        );
+
+        if !transactions.is_empty() {
+            db.update_transactions_parsed("decoded_transactions_parsed", transactions)
+                .await
+                .unwrap();
+        }
+
+        Ok(())
+    }
+
+    fn decode_with_abi(
+        &self,
+        contract: &Contract,
+        source: &'static str,
+        input: &[u8],
+    ) -> Option<(String, String, Value, &'static str)> {
+        let function = contract
+            .functions()
+            .find(|function| function.short_signature() == input[..4])?;
+
+        let tokens = function.decode_input(&input[4..]).ok()?;
+
+        let mut params = Map::new();
+
+        for (i, (param, token)) in function.inputs.iter().zip(tokens.iter()).enumerate() {
+            let name = if param.name.is_empty() {
+                format!("arg{}", i)
+            } else {
+                param.name.clone()
+            };
+
+            params.insert(name, format_token(token));
+        }
+
+        Some((
+            function.name.clone(),
+            function.abi_signature(),
+            Value::Object(params),
+            source,
+        ))
+    }
+
+    /// Signatures don't include the arguments names, so they are stored by position.
+    fn decode_with_signature(
+        &self,
+        signature: &str,
+        input: &[u8],
+    ) -> Option<(String, String, Value, &'static str)> {
+        let (name, inputs): (String, Vec<ParamType>) = parse_signature(signature)?;
+
+        let tokens = ethabi::decode(&inputs, &input[4..]).ok()?;
+
+        let mut params = Map::new();
+
+        for (i, token) in tokens.iter().enumerate() {
+            params.insert(format!("arg{}", i), format_token(token));
+        }
+
+        Some((
+            name,
+            signature.to_owned(),
+            Value::Object(params),
+            "signature",
+        ))
+    }
+}
@@ -0,0 +1,846 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    chains::chains::get_chain,
+    db::{
+        db::{get_chunks, Database},
+        models::models::DatabaseLog,
+    },
+    utils::{format_address, format_number},
+};
+use anyhow::Result;
+use ethabi::{Address, Event, Token};
+use ethers::{
+    abi::parse_abi,
+    prelude::abigen,
+    providers::{Http, Provider},
+    types::{I256, U256},
+};
+use field_count::FieldCount;
+use futures::future::join_all;
+use log::info;
+use rand::seq::SliceRandom;
+use sqlx::QueryBuilder;
+
+/// Events shared by the Uniswap V2 and V3 factories and pools and all their forks.
+pub const DEX_EVENTS: &[&str] = &[
+    "event PairCreated(address indexed token0, address indexed token1, address pair, uint256 allPairsLength)",
+    "event PoolCreated(address indexed token0, address indexed token1, uint24 indexed fee, int24 tickSpacing, address pool)",
+    "event Swap(address indexed sender, uint256 amount0In, uint256 amount1In, uint256 amount0Out, uint256 amount1Out, address indexed to)",
+    "event Mint(address indexed sender, uint256 amount0, uint256 amount1)",
+    "event Burn(address indexed sender, uint256 amount0, uint256 amount1, address indexed to)",
+    "event Sync(uint112 reserve0, uint112 reserve1)",
+    "event Swap(address indexed sender, address indexed recipient, int256 amount0, int256 amount1, uint160 sqrtPriceX96, uint128 liquidity, int24 tick)",
+    "event Mint(address sender, address indexed owner, int24 indexed tickLower, int24 indexed tickUpper, uint128 amount, uint256 amount0, uint256 amount1)",
+    "event Burn(address indexed owner, int24 indexed tickLower, int24 indexed tickUpper, uint128 amount, uint256 amount0, uint256 amount1)",
+    "event Collect(address indexed owner, address recipient, int24 indexed tickLower, int24 indexed tickUpper, uint128 amount0, uint128 amount1)",
+];
+
+pub const UNISWAP_V2: &str = "uniswap-v2";
+
+pub const UNISWAP_V3: &str = "uniswap-v3";
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DatabaseLogWithTransaction {
+    #[sqlx(flatten)]
+    pub log: DatabaseLog,
+    pub block_number: i64,
+    pub from_address: String,
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseDexPool {
+    pub address: String,
+    pub chain: String,
+    pub factory: Option<String>,
+    pub fee: Option<i64>,
+    pub protocol: String,
+    pub token0: String,
+    pub token1: String,
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseDexTrade {
+    pub amount_in: String,
+    pub amount_out: String,
+    pub block_number: i64,
+    pub chain: String,
+    pub hash: String,
+    pub log_index: i64,
+    pub pool: String,
+    pub protocol: String,
+    pub recipient: String,
+    pub sender: String,
+    pub token_in: String,
+    pub token_out: String,
+    pub trader: String,
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseDexLiquidityEvent {
+    pub amount0: String,
+    pub amount1: String,
+    pub block_number: i64,
+    pub chain: String,
+    pub event: String,
+    pub hash: String,
+    pub liquidity: Option<String>,
+    pub log_index: i64,
+    pub owner: String,
+    pub pool: String,
+    pub protocol: String,
+    pub provider: String,
+    pub tick_lower: Option<i64>,
+    pub tick_upper: Option<i64>,
+    pub token0: String,
+    pub token1: String,
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseDexReserves {
+    pub block_number: i64,
+    pub chain: String,
+    pub log_index: i64,
+    pub pool: String,
+    pub reserve0: String,
+    pub reserve1: String,
+}
+
+/// Pool event decoded before knowing the pool tokens.
+struct PoolEvent<'a> {
+    log: &'a DatabaseLogWithTransaction,
+    name: String,
+    params: HashMap<String, Token>,
+    protocol: &'static str,
+}
+
+pub struct DexTrades {}
+
+abigen!(
+    UniswapPool,
+    r#"[
+        function token0() external view returns (address)
+        function token1() external view returns (address)
+        function factory() external view returns (address)
+        function fee() external view returns (uint24)
+    ]"#,
+);
+
+impl DexTrades {
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseLogWithTransaction>> {
+        let connection = db.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseLogWithTransaction>(
+            "SELECT logs.*, transactions.block_number, transactions.from_address FROM logs INNER JOIN transactions ON logs.hash = transactions.hash WHERE logs.dex_trades_parsed = false LIMIT 500",
+        )
+        .fetch_all(connection)
+        .await;
+
+        match rows {
+            Ok(logs) => Ok(logs),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn parse(&self, db: &Database, logs: &[DatabaseLogWithTransaction]) -> Result<()> {
+        let events = self.get_events();
+
+        let mut pools: HashMap<(String, String), DatabaseDexPool> = HashMap::new();
+
+        let mut pool_events: Vec<PoolEvent> = Vec::new();
+
+        let mut db_parsed_logs: Vec<DatabaseLog> = Vec::new();
+
+        for log_with_transaction in logs {
+            let log = &log_with_transaction.log;
+
+            db_parsed_logs.push(log.to_owned());
+
+            let event = match log.topics.first() {
+                Some(Some(topic)) => match events.get(topic) {
+                    Some(event) => event,
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            let raw_log = match log.to_raw_log() {
+                Some(raw_log) => raw_log,
+                None => continue,
+            };
+
+            // The same topic can be emitted by unrelated contracts with a different amount of
+            // indexed parameters, those logs are not pool events.
+            let decoded = match event.parse_log(raw_log) {
+                Ok(decoded) => decoded,
+                Err(_) => continue,
+            };
+
+            let params: HashMap<String, Token> = decoded
+                .params
+                .into_iter()
+                .map(|param| (param.name, param.value))
+                .collect();
+
+            let protocol = match (event.name.as_str(), event.inputs.len()) {
+                ("PairCreated", _) | ("Swap", 6) | ("Mint", 3) | ("Burn", 4) | ("Sync", _) => {
+                    UNISWAP_V2
+                }
+                _ => UNISWAP_V3,
+            };
+
+            if event.name == "PairCreated" || event.name == "PoolCreated" {
+                if let Some(pool) = self.parse_pool_created(log, &params, protocol) {
+                    pools.insert((pool.address.clone(), pool.chain.clone()), pool);
+                }
+
+                continue;
+            }
+
+            pool_events.push(PoolEvent {
+                log: log_with_transaction,
+                name: event.name.clone(),
+                params,
+                protocol,
+            });
+        }
+
+        let missing_pools: Vec<(String, String)> = pool_events
+            .iter()
+            .map(|event| (event.log.log.address.clone(), event.log.log.chain.clone()))
+            .filter(|pool| !pools.contains_key(pool))
+            .collect::<HashSet<(String, String)>>()
+            .into_iter()
+            .collect();
+
+        let mut known_pools: HashMap<(String, String), DatabaseDexPool> = HashMap::new();
+
+        for pool in self.get_pools(db, &missing_pools).await? {
+            known_pools.insert((pool.address.clone(), pool.chain.clone()), pool);
+        }
+
+        let unknown_pools: Vec<((String, String), &'static str)> = missing_pools
+            .into_iter()
+            .filter(|pool| !known_pools.contains_key(pool))
+            .map(|(address, chain)| {
+                let protocol = pool_events
+                    .iter()
+                    .find(|event| event.log.log.address == address && event.log.log.chain == chain)
+                    .map(|event| event.protocol)
+                    .unwrap_or(UNISWAP_V2);
+
+                ((address, chain), protocol)
+            })
+            .collect();
+
+        for pool in self.resolve_pools(db, unknown_pools).await? {
+            pools.insert((pool.address.clone(), pool.chain.clone()), pool);
+        }
+
+        let db_pools: Vec<DatabaseDexPool> = pools.values().cloned().collect();
+
+        pools.extend(known_pools);
+
+        let mut db_trades: Vec<DatabaseDexTrade> = Vec::new();
+
+        let mut db_liquidity_events: Vec<DatabaseDexLiquidityEvent> = Vec::new();
+
+        let mut reserves: HashMap<(String, String), DatabaseDexReserves> = HashMap::new();
+
+        for event in pool_events {
+            let log = &event.log.log;
+
+            let pool = match pools.get(&(log.address.clone(), log.chain.clone())) {
+                Some(pool) => pool,
+                None => continue,
+            };
+
+            match event.name.as_str() {
+                "Swap" => {
+                    if let Some(trade) = self.parse_swap(&event, pool) {
+                        db_trades.push(trade);
+                    }
+                }
+                "Mint" | "Burn" | "Collect" => {
+                    if let Some(liquidity_event) = self.parse_liquidity_event(&event, pool) {
+                        db_liquidity_events.push(liquidity_event);
+                    }
+                }
+                "Sync" => {
+                    let (reserve0, reserve1) = match (
+                        get_uint(&event.params, "reserve0"),
+                        get_uint(&event.params, "reserve1"),
+                    ) {
+                        (Some(reserve0), Some(reserve1)) => (reserve0, reserve1),
+                        _ => continue,
+                    };
+
+                    let db_reserves = DatabaseDexReserves {
+                        block_number: event.log.block_number,
+                        chain: log.chain.clone(),
+                        log_index: log.log_index,
+                        pool: log.address.clone(),
+                        reserve0: format_number(reserve0),
+                        reserve1: format_number(reserve1),
+                    };
+
+                    let id = (db_reserves.pool.clone(), db_reserves.chain.clone());
+
+                    let is_latest = match reserves.get(&id) {
+                        Some(stored) => {
+                            (stored.block_number, stored.log_index)
+                                < (db_reserves.block_number, db_reserves.log_index)
+                        }
+                        None => true,
+                    };
+
+                    if is_latest {
+                        reserves.insert(id, db_reserves);
+                    }
+                }
+                _ => continue,
+            }
+        }
+
+        let db_reserves: Vec<DatabaseDexReserves> = reserves.into_values().collect();
+
+        if !db_pools.is_empty() {
+            self.store_pools(db, &db_pools).await?;
+        }
+
+        if !db_trades.is_empty() {
+            self.store_trades(db, &db_trades).await?;
+        }
+
+        if !db_liquidity_events.is_empty() {
+            self.store_liquidity_events(db, &db_liquidity_events)
+                .await?;
+        }
+
+        if !db_reserves.is_empty() {
+            self.store_reserves(db, &db_reserves).await?;
+        }
+
+        info!(
+            "DexTrades: Inserted {} pools {} trades {} liquidity events and {} reserves to the database.",
+            db_pools.len(),
+            db_trades.len(),
+            db_liquidity_events.len(),
+            db_reserves.len()
+        );
+
+        if !db_parsed_logs.is_empty() {
+            db.update_logs_parsed("dex_trades_parsed", &db_parsed_logs)
+                .await
+                .unwrap();
+        }
+
+        Ok(())
+    }
+
+    /// Returns the dex events by their topic.
+    pub fn get_events(&self) -> HashMap<String, Event> {
+        let abi = parse_abi(DEX_EVENTS).expect("Unable to parse dex events");
+
+        abi.events()
+            .map(|event| (format!("{:?}", event.signature()), event.clone()))
+            .collect()
+    }
+
+    pub async fn get_pools(
+        &self,
+        db: &Database,
+        pools: &[(String, String)],
+    ) -> Result<Vec<DatabaseDexPool>> {
+        let connection = db.get_connection();
+
+        let mut db_pools = Vec::new();
+
+        if pools.is_empty() {
+            return Ok(db_pools);
+        }
+
+        let chunks = get_chunks(pools.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder =
+                QueryBuilder::new("SELECT * FROM dex_pools WHERE (address, chain) IN ");
+
+            query_builder.push_tuples(&pools[start..end], |mut row, (address, chain)| {
+                row.push_bind(address.clone()).push_bind(chain.clone());
+            });
+
+            let rows = query_builder
+                .build_query_as::<DatabaseDexPool>()
+                .fetch_all(connection)
+                .await;
+
+            match rows {
+                Ok(mut rows) => db_pools.append(&mut rows),
+                Err(_) => continue,
+            }
+        }
+
+        Ok(db_pools)
+    }
+
+    /// Pools created before the indexed range or by unknown factories are resolved through
+    /// the pool contract itself. Pools that failed to resolve are retried a day later.
+    pub async fn resolve_pools(
+        &self,
+        db: &Database,
+        pools: Vec<((String, String), &'static str)>,
+    ) -> Result<Vec<DatabaseDexPool>> {
+        let keys: Vec<(String, String)> = pools.iter().map(|(id, _)| id.clone()).collect();
+
+        let unresolved_pools = self.get_unresolved_pools(db, &keys).await?;
+
+        let mut work = vec![];
+
+        let mut work_pools: Vec<(String, String)> = Vec::new();
+
+        for ((address, chain), protocol) in pools {
+            let id = (address.clone(), chain.clone());
+
+            if unresolved_pools.contains(&id) {
+                continue;
+            }
+
+            work_pools.push(id);
+
+            work.push(self.get_pool_metadata(address, chain, protocol));
+        }
+
+        let mut resolved_pools: Vec<DatabaseDexPool> = Vec::new();
+
+        let mut failed_pools: Vec<(String, String)> = Vec::new();
+
+        for (id, pool) in work_pools.into_iter().zip(join_all(work).await) {
+            match pool {
+                Some(pool) => resolved_pools.push(pool),
+                None => failed_pools.push(id),
+            }
+        }
+
+        if !failed_pools.is_empty() {
+            self.store_unresolved_pools(db, &failed_pools).await?;
+        }
+
+        Ok(resolved_pools)
+    }
+
+    /// Returns the pools that recently failed to resolve through their contract.
+    async fn get_unresolved_pools(
+        &self,
+        db: &Database,
+        pools: &[(String, String)],
+    ) -> Result<HashSet<(String, String)>> {
+        let connection = db.get_connection();
+
+        let mut unresolved_pools = HashSet::new();
+
+        if pools.is_empty() {
+            return Ok(unresolved_pools);
+        }
+
+        let chunks = get_chunks(pools.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "SELECT address, chain FROM dex_pools_unresolved WHERE next_attempt_at > now() AND (address, chain) IN ",
+            );
+
+            query_builder.push_tuples(&pools[start..end], |mut row, (address, chain)| {
+                row.push_bind(address.clone()).push_bind(chain.clone());
+            });
+
+            let rows = query_builder
+                .build_query_as::<(String, String)>()
+                .fetch_all(connection)
+                .await;
+
+            match rows {
+                Ok(rows) => unresolved_pools.extend(rows),
+                Err(_) => continue,
+            }
+        }
+
+        Ok(unresolved_pools)
+    }
+
+    async fn store_unresolved_pools(
+        &self,
+        db: &Database,
+        pools: &[(String, String)],
+    ) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(pools.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO dex_pools_unresolved (address, chain, next_attempt_at) ",
+            );
+
+            query_builder.push_values(&pools[start..end], |mut row, (address, chain)| {
+                row.push_bind(address.clone())
+                    .push_bind(chain.clone())
+                    .push_unseparated(", now() + INTERVAL '1 day'");
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store unresolved dex pools into database");
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_pool_metadata(
+        &self,
+        address: String,
+        chain: String,
+        protocol: &'static str,
+    ) -> Option<DatabaseDexPool> {
+        let rpcs = get_chain(chain.to_string()).get_rpcs();
+
+        let rpc = rpcs.choose(&mut rand::thread_rng())?;
+
+        let provider = match Provider::<Http>::try_from(rpc.as_str()) {
+            Ok(provider) => provider,
+            Err(_) => return None,
+        };
+
+        let client = Arc::new(provider);
+
+        let pool = UniswapPool::new(address.parse::<Address>().ok()?, Arc::clone(&client));
+
+        let token0 = pool.token_0().call().await.ok()?;
+
+        let token1 = pool.token_1().call().await.ok()?;
+
+        let factory: Option<String> = match pool.factory().call().await {
+            Ok(factory) => Some(format_address(factory)),
+            Err(_) => None,
+        };
+
+        let fee: Option<i64> = if protocol == UNISWAP_V3 {
+            match pool.fee().call().await {
+                Ok(fee) => Some(fee as i64),
+                Err(_) => None,
+            }
+        } else {
+            None
+        };
+
+        Some(DatabaseDexPool {
+            address,
+            chain,
+            factory,
+            fee,
+            protocol: protocol.to_owned(),
+            token0: format_address(token0),
+            token1: format_address(token1),
+        })
+    }
+
+    fn parse_pool_created(
+        &self,
+        log: &DatabaseLog,
+        params: &HashMap<String, Token>,
+        protocol: &'static str,
+    ) -> Option<DatabaseDexPool> {
+        let address = match protocol {
+            UNISWAP_V2 => get_address(params, "pair")?,
+            _ => get_address(params, "pool")?,
+        };
+
+        let fee = get_uint(params, "fee").map(|fee| fee.low_u64() as i64);
+
+        Some(DatabaseDexPool {
+            address,
+            chain: log.chain.clone(),
+            factory: Some(log.address.clone()),
+            fee,
+            protocol: protocol.to_owned(),
+            token0: get_address(params, "token0")?,
+            token1: get_address(params, "token1")?,
+        })
+    }
+
+    /// Swaps are normalized from the pool point of view, the token with a positive balance
+    /// change is the one sold by the trader.
+    fn parse_swap(&self, event: &PoolEvent, pool: &DatabaseDexPool) -> Option<DatabaseDexTrade> {
+        let params = &event.params;
+
+        let (token0_in, amount0, amount1, recipient) = match event.protocol {
+            UNISWAP_V2 => {
+                let amount0_in = get_uint(params, "amount0In")?;
+                let amount1_in = get_uint(params, "amount1In")?;
+                let amount0_out = get_uint(params, "amount0Out")?;
+                let amount1_out = get_uint(params, "amount1Out")?;
+
+                if amount0_in > amount0_out {
+                    (
+                        true,
+                        amount0_in - amount0_out,
+                        amount1_out.saturating_sub(amount1_in),
+                        get_address(params, "to")?,
+                    )
+                } else {
+                    (
+                        false,
+                        amount0_out - amount0_in,
+                        amount1_in.saturating_sub(amount1_out),
+                        get_address(params, "to")?,
+                    )
+                }
+            }
+            _ => {
+                let amount0 = get_int(params, "amount0")?;
+                let amount1 = get_int(params, "amount1")?;
+
+                (
+                    amount0.is_positive(),
+                    amount0.into_sign_and_abs().1,
+                    amount1.into_sign_and_abs().1,
+                    get_address(params, "recipient")?,
+                )
+            }
+        };
+
+        let (token_in, amount_in, token_out, amount_out) = if token0_in {
+            (&pool.token0, amount0, &pool.token1, amount1)
+        } else {
+            (&pool.token1, amount1, &pool.token0, amount0)
+        };
+
+        let log = &event.log.log;
+
+        Some(DatabaseDexTrade {
+            amount_in: format_number(amount_in),
+            amount_out: format_number(amount_out),
+            block_number: event.log.block_number,
+            chain: log.chain.clone(),
+            hash: log.hash.clone(),
+            log_index: log.log_index,
+            pool: log.address.clone(),
+            protocol: event.protocol.to_owned(),
+            recipient,
+            sender: get_address(params, "sender")?,
+            token_in: token_in.clone(),
+            token_out: token_out.clone(),
+            trader: event.log.from_address.clone(),
+        })
+    }
+
+    fn parse_liquidity_event(
+        &self,
+        event: &PoolEvent,
+        pool: &DatabaseDexPool,
+    ) -> Option<DatabaseDexLiquidityEvent> {
+        let params = &event.params;
+
+        let owner = match event.protocol {
+            UNISWAP_V2 => get_address(params, "sender")?,
+            _ => get_address(params, "owner")?,
+        };
+
+        let log = &event.log.log;
+
+        Some(DatabaseDexLiquidityEvent {
+            amount0: format_number(get_uint(params, "amount0")?),
+            amount1: format_number(get_uint(params, "amount1")?),
+            block_number: event.log.block_number,
+            chain: log.chain.clone(),
+            event: event.name.to_lowercase(),
+            hash: log.hash.clone(),
+            liquidity: get_uint(params, "amount").map(format_number),
+            log_index: log.log_index,
+            owner,
+            pool: log.address.clone(),
+            protocol: event.protocol.to_owned(),
+            provider: event.log.from_address.clone(),
+            tick_lower: get_int(params, "tickLower").map(|tick| tick.low_i64()),
+            tick_upper: get_int(params, "tickUpper").map(|tick| tick.low_i64()),
+            token0: pool.token0.clone(),
+            token1: pool.token1.clone(),
+        })
+    }
+
+    pub async fn store_pools(&self, db: &Database, pools: &[DatabaseDexPool]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(pools.len(), DatabaseDexPool::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO dex_pools (address, chain, factory, fee, protocol, token0, token1) ",
+            );
+
+            query_builder.push_values(&pools[start..end], |mut row, pool| {
+                row.push_bind(pool.address.clone())
+                    .push_bind(pool.chain.clone())
+                    .push_bind(pool.factory.clone())
+                    .push_bind(pool.fee)
+                    .push_bind(pool.protocol.clone())
+                    .push_bind(pool.token0.clone())
+                    .push_bind(pool.token1.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store dex pools into database");
+        }
+
+        Ok(())
+    }
+
+    async fn store_trades(&self, db: &Database, trades: &[DatabaseDexTrade]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(trades.len(), DatabaseDexTrade::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO dex_trades (amount_in, amount_out, block_number, chain, hash, log_index, pool, protocol, recipient, sender, token_in, token_out, trader) ",
+            );
+
+            query_builder.push_values(&trades[start..end], |mut row, trade| {
+                row.push_bind(trade.amount_in.clone())
+                    .push_bind(trade.amount_out.clone())
+                    .push_bind(trade.block_number)
+                    .push_bind(trade.chain.clone())
+                    .push_bind(trade.hash.clone())
+                    .push_bind(trade.log_index)
+                    .push_bind(trade.pool.clone())
+                    .push_bind(trade.protocol.clone())
+                    .push_bind(trade.recipient.clone())
+                    .push_bind(trade.sender.clone())
+                    .push_bind(trade.token_in.clone())
+                    .push_bind(trade.token_out.clone())
+                    .push_bind(trade.trader.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store dex trades into database");
+        }
+
+        Ok(())
+    }
+
+    async fn store_liquidity_events(
+        &self,
+        db: &Database,
+        liquidity_events: &[DatabaseDexLiquidityEvent],
+    ) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(
+            liquidity_events.len(),
+            DatabaseDexLiquidityEvent::field_count(),
+        );
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO dex_liquidity_events (amount0, amount1, block_number, chain, event, hash, liquidity, log_index, owner, pool, protocol, provider, tick_lower, tick_upper, token0, token1) ",
+            );
+
+            query_builder.push_values(&liquidity_events[start..end], |mut row, liquidity_event| {
+                row.push_bind(liquidity_event.amount0.clone())
+                    .push_bind(liquidity_event.amount1.clone())
+                    .push_bind(liquidity_event.block_number)
+                    .push_bind(liquidity_event.chain.clone())
+                    .push_bind(liquidity_event.event.clone())
+                    .push_bind(liquidity_event.hash.clone())
+                    .push_bind(liquidity_event.liquidity.clone())
+                    .push_bind(liquidity_event.log_index)
+                    .push_bind(liquidity_event.owner.clone())
+                    .push_bind(liquidity_event.pool.clone())
+                    .push_bind(liquidity_event.protocol.clone())
+                    .push_bind(liquidity_event.provider.clone())
+                    .push_bind(liquidity_event.tick_lower)
+                    .push_bind(liquidity_event.tick_upper)
+                    .push_bind(liquidity_event.token0.clone())
+                    .push_bind(liquidity_event.token1.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store dex liquidity events into database");
+        }
+
+        Ok(())
+    }
+
+    async fn store_reserves(&self, db: &Database, reserves: &[DatabaseDexReserves]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(reserves.len(), DatabaseDexReserves::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "INSERT INTO dex_reserves (block_number, chain, log_index, pool, reserve0, reserve1) ",
+            );
+
+            query_builder.push_values(&reserves[start..end], |mut row, db_reserves| {
+                row.push_bind(db_reserves.block_number)
+                    .push_bind(db_reserves.chain.clone())
+                    .push_bind(db_reserves.log_index)
+                    .push_bind(db_reserves.pool.clone())
+                    .push_bind(db_reserves.reserve0.clone())
+                    .push_bind(db_reserves.reserve1.clone());
+            });
+
+            // Logs are not parsed in block order, so older reserves must never overwrite
+            // newer ones.
+            query_builder.push(" ON CONFLICT (pool, chain) DO UPDATE SET block_number = excluded.block_number, log_index = excluded.log_index, reserve0 = excluded.reserve0, reserve1 = excluded.reserve1 WHERE (dex_reserves.block_number, dex_reserves.log_index) < (excluded.block_number, excluded.log_index)");
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store dex reserves into database");
+        }
+
+        Ok(())
+    }
+}
+
+fn get_address(params: &HashMap<String, Token>, name: &str) -> Option<String> {
+    match params.get(name) {
+        Some(Token::Address(address)) => Some(format_address(*address)),
+        _ => None,
+    }
+}
+
+fn get_uint(params: &HashMap<String, Token>, name: &str) -> Option<U256> {
+    match params.get(name) {
+        Some(Token::Uint(value)) => Some(*value),
+        _ => None,
+    }
+}
+
+fn get_int(params: &HashMap<String, Token>, name: &str) -> Option<I256> {
+    match params.get(name) {
+        Some(Token::Int(value)) => Some(I256::from_raw(*value)),
+        _ => None,
+    }
+}
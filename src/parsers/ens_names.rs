@@ -0,0 +1,548 @@
+use std::collections::HashMap;
+
+use crate::{
+    db::{
+        db::{get_chunks, Database},
+        models::models::{DatabaseLog, DatabaseLogWithBlock},
+    },
+    utils::format_address,
+};
+use anyhow::Result;
+use ethabi::{ParamType, Token};
+use ethers::utils::keccak256;
+use field_count::FieldCount;
+use log::info;
+use sqlx::QueryBuilder;
+
+/// Current registry, it falls back to the legacy registry for the nodes it has no record of.
+pub const ENS_REGISTRY: &str = "0x00000000000c2e074ec69a0dfb2997ba6c7d2e1e";
+
+pub const ENS_LEGACY_REGISTRY: &str = "0x314159265dd8dbb310642f98f50c066173c1259b";
+
+/// Registries that emit `NewOwner`, `NewResolver` and `Transfer`.
+pub const ENS_REGISTRIES: &[&str] = &[ENS_REGISTRY, ENS_LEGACY_REGISTRY];
+
+/// Namehash of `addr.reverse`, parent of the reverse records of every address.
+pub const ADDR_REVERSE_NODE: &str =
+    "0x91d1777781884d03a6757a803996e38de2a42967fb37eeaca72729271025a9e2";
+
+pub const ENS_RECORD_OWNER: &str = "owner";
+pub const ENS_RECORD_RESOLVER: &str = "resolver";
+pub const ENS_RECORD_ADDR: &str = "addr";
+pub const ENS_RECORD_NAME: &str = "name";
+
+/// Levels of names filled in a single batch, when labels or parents arrive after the nodes.
+pub const MAX_NAME_DEPTH: usize = 10;
+
+/// Node of the ENS tree, the name is only known once the label and the parent name are.
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseEnsName {
+    pub labelhash: String,
+    pub node: String,
+    pub parent: String,
+}
+
+/// Label revealed by a registration, checked against its hash.
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseEnsLabel {
+    pub label: String,
+    pub labelhash: String,
+}
+
+/// Change of the owner or resolver of a node in the registry, or of the address or name
+/// records in a resolver. `contract` is the emitting registry or resolver, resolver records
+/// are only valid while `contract` is the resolver of the node.
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseEnsRecord {
+    pub block_number: i64,
+    pub contract: String,
+    pub hash: String,
+    pub kind: String,
+    pub log_index: i64,
+    pub node: String,
+    pub value: String,
+}
+
+pub struct ENSNames {}
+
+impl ENSNames {
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseLogWithBlock>> {
+        let connection = db.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseLogWithBlock>(
+            "SELECT logs.*, transactions.block_number FROM logs INNER JOIN transactions ON logs.hash = transactions.hash WHERE logs.chain = 'ethereum' AND logs.ens_names_parsed = false LIMIT 500",
+        )
+        .fetch_all(connection)
+        .await;
+
+        match rows {
+            Ok(logs) => Ok(logs),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Stores the nodes created in the registry, the labels revealed by the registrar
+    /// controllers and the history of the owner, resolver, address and name records. Only
+    /// Ethereum logs are fetched.
+    pub async fn parse(&self, db: &Database, logs: &[DatabaseLogWithBlock]) -> Result<()> {
+        let signature = |event: &str| format!("0x{}", hex::encode(keccak256(event)));
+
+        let new_owner = signature("NewOwner(bytes32,bytes32,address)");
+        let new_resolver = signature("NewResolver(bytes32,address)");
+        let transfer = signature("Transfer(bytes32,address)");
+        let addr_changed = signature("AddrChanged(bytes32,address)");
+        let name_changed = signature("NameChanged(bytes32,string)");
+        let reverse_claimed = signature("ReverseClaimed(address,bytes32)");
+
+        // Controllers revealing the label in the data, the arguments after the name differ.
+        let name_registered: HashMap<String, Vec<ParamType>> = HashMap::from([
+            (
+                signature("NameRegistered(string,bytes32,address,uint256,uint256)"),
+                vec![
+                    ParamType::String,
+                    ParamType::Uint(256),
+                    ParamType::Uint(256),
+                ],
+            ),
+            (
+                signature("NameRegistered(string,bytes32,address,uint256,uint256,uint256)"),
+                vec![
+                    ParamType::String,
+                    ParamType::Uint(256),
+                    ParamType::Uint(256),
+                    ParamType::Uint(256),
+                ],
+            ),
+        ]);
+
+        let mut db_names: HashMap<String, DatabaseEnsName> = HashMap::new();
+
+        let mut db_labels: HashMap<String, DatabaseEnsLabel> = HashMap::new();
+
+        let mut db_records: Vec<DatabaseEnsRecord> = Vec::new();
+
+        let mut db_parsed_logs: Vec<DatabaseLog> = Vec::new();
+
+        for log_with_block in logs {
+            let log = &log_with_block.log;
+
+            db_parsed_logs.push(log.to_owned());
+
+            if log.removed {
+                continue;
+            }
+
+            let topics: Vec<&String> = log.topics.iter().flatten().collect();
+
+            let topic = match topics.first() {
+                Some(topic) => topic.to_string(),
+                None => continue,
+            };
+
+            let data = match hex::decode(log.data.trim_start_matches("0x")) {
+                Ok(data) => data,
+                Err(_) => continue,
+            };
+
+            let record = |node: &str, kind: &str, value: String| DatabaseEnsRecord {
+                block_number: log_with_block.block_number,
+                contract: log.address.clone(),
+                hash: log.hash.clone(),
+                kind: kind.to_owned(),
+                log_index: log.log_index,
+                node: node.to_owned(),
+                value,
+            };
+
+            let from_registry = ENS_REGISTRIES.contains(&log.address.as_str());
+
+            if from_registry && topic == new_owner && topics.len() == 3 {
+                let owner = match decode_address(&data) {
+                    Some(owner) => owner,
+                    None => continue,
+                };
+
+                let node = get_subnode(topics[1], topics[2]);
+
+                db_names.insert(
+                    node.clone(),
+                    DatabaseEnsName {
+                        labelhash: topics[2].to_lowercase(),
+                        node: node.clone(),
+                        parent: topics[1].to_lowercase(),
+                    },
+                );
+
+                db_records.push(record(&node, ENS_RECORD_OWNER, owner));
+            } else if from_registry && topic == transfer && topics.len() == 2 {
+                if let Some(owner) = decode_address(&data) {
+                    db_records.push(record(&topics[1].to_lowercase(), ENS_RECORD_OWNER, owner));
+                }
+            } else if from_registry && topic == new_resolver && topics.len() == 2 {
+                if let Some(resolver) = decode_address(&data) {
+                    db_records.push(record(
+                        &topics[1].to_lowercase(),
+                        ENS_RECORD_RESOLVER,
+                        resolver,
+                    ));
+                }
+            } else if topic == addr_changed && topics.len() == 2 {
+                if let Some(address) = decode_address(&data) {
+                    db_records.push(record(&topics[1].to_lowercase(), ENS_RECORD_ADDR, address));
+                }
+            } else if topic == name_changed && topics.len() == 2 {
+                if let Some(name) = decode_string(&[ParamType::String], &data) {
+                    db_records.push(record(&topics[1].to_lowercase(), ENS_RECORD_NAME, name));
+                }
+            } else if topic == reverse_claimed && topics.len() == 3 {
+                let address = match decode_address(&hex_to_bytes(topics[1])) {
+                    Some(address) => address,
+                    None => continue,
+                };
+
+                let label = address.trim_start_matches("0x").to_owned();
+
+                let labelhash = format!("0x{}", hex::encode(keccak256(&label)));
+
+                let node = get_subnode(ADDR_REVERSE_NODE, &labelhash);
+
+                // The reverse registrar always claims the node of the address.
+                if node != topics[2].to_lowercase() {
+                    continue;
+                }
+
+                db_names.insert(
+                    node.clone(),
+                    DatabaseEnsName {
+                        labelhash: labelhash.clone(),
+                        node,
+                        parent: ADDR_REVERSE_NODE.to_owned(),
+                    },
+                );
+
+                db_labels.insert(labelhash.clone(), DatabaseEnsLabel { label, labelhash });
+            } else if let Some(params) = name_registered.get(&topic) {
+                if topics.len() != 3 {
+                    continue;
+                }
+
+                let label = match decode_string(params, &data) {
+                    Some(label) => label,
+                    None => continue,
+                };
+
+                let labelhash = format!("0x{}", hex::encode(keccak256(&label)));
+
+                // Any contract can emit the event, only labels matching their hash are kept.
+                if labelhash != topics[1].to_lowercase() {
+                    continue;
+                }
+
+                db_labels.insert(labelhash.clone(), DatabaseEnsLabel { label, labelhash });
+            }
+        }
+
+        let db_names: Vec<DatabaseEnsName> = db_names.into_values().collect();
+
+        let db_labels: Vec<DatabaseEnsLabel> = db_labels.into_values().collect();
+
+        if !db_labels.is_empty() {
+            self.store_labels(db, &db_labels).await?;
+        }
+
+        if !db_names.is_empty() {
+            self.store_names(db, &db_names).await?;
+        }
+
+        if !db_records.is_empty() {
+            self.store_records(db, &db_records).await?;
+        }
+
+        if !db_names.is_empty() || !db_labels.is_empty() {
+            let labelhashes: Vec<String> = db_labels
+                .iter()
+                .map(|label| label.labelhash.clone())
+                .chain(db_names.iter().map(|name| name.labelhash.clone()))
+                .collect();
+
+            let nodes: Vec<String> = db_names.iter().map(|name| name.node.clone()).collect();
+
+            self.fill_names(db, &labelhashes, &nodes).await?;
+        }
+
+        info!(
+            "ENSNames: Inserted {} nodes {} labels and {} records to the database.",
+            db_names.len(),
+            db_labels.len(),
+            db_records.len()
+        );
+
+        if !db_parsed_logs.is_empty() {
+            db.update_logs_parsed("ens_names_parsed", &db_parsed_logs)
+                .await
+                .unwrap();
+        }
+
+        Ok(())
+    }
+
+    /// Returns the address a name resolves to through the current resolver of its node.
+    pub async fn resolve_name(&self, db: &Database, name: &str) -> Result<Option<String>> {
+        let node = namehash(&name.to_lowercase());
+
+        self.get_resolver_record(db, &node, ENS_RECORD_ADDR).await
+    }
+
+    /// Returns the primary name of an address, only when the name resolves back to it.
+    pub async fn lookup_address(&self, db: &Database, address: &str) -> Result<Option<String>> {
+        let address = address.to_lowercase();
+
+        let node = namehash(&format!(
+            "{}.addr.reverse",
+            address.trim_start_matches("0x")
+        ));
+
+        let name = match self.get_resolver_record(db, &node, ENS_RECORD_NAME).await? {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+
+        match self.resolve_name(db, &name).await? {
+            Some(resolved) if resolved == address => Ok(Some(name)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the owner or resolver of a node in the registry. Records of the legacy registry
+    /// are ignored once the node exists in the current registry, as the registry only falls
+    /// back to the legacy one for the nodes it has no record of.
+    pub async fn get_registry_record(
+        &self,
+        db: &Database,
+        node: &str,
+        kind: &str,
+    ) -> Result<Option<String>> {
+        let connection = db.get_connection();
+
+        let value = sqlx::query_scalar::<_, String>(
+            "SELECT value FROM ens_records WHERE node = $1 AND kind = $2 AND contract = (CASE WHEN EXISTS (SELECT 1 FROM ens_records WHERE node = $1 AND kind IN ($3, $4) AND contract = $5) THEN $5 ELSE $6 END) ORDER BY block_number DESC, log_index DESC LIMIT 1",
+        )
+        .bind(node.to_owned())
+        .bind(kind.to_owned())
+        .bind(ENS_RECORD_OWNER)
+        .bind(ENS_RECORD_RESOLVER)
+        .bind(ENS_REGISTRY)
+        .bind(ENS_LEGACY_REGISTRY)
+        .fetch_optional(connection)
+        .await?;
+
+        Ok(value)
+    }
+
+    async fn get_resolver_record(
+        &self,
+        db: &Database,
+        node: &str,
+        kind: &str,
+    ) -> Result<Option<String>> {
+        let resolver = match self
+            .get_registry_record(db, node, ENS_RECORD_RESOLVER)
+            .await?
+        {
+            Some(resolver) => resolver,
+            None => return Ok(None),
+        };
+
+        let connection = db.get_connection();
+
+        let value = sqlx::query_scalar::<_, String>(
+            "SELECT value FROM ens_records WHERE node = $1 AND kind = $2 AND contract = $3 ORDER BY block_number DESC, log_index DESC LIMIT 1",
+        )
+        .bind(node.to_owned())
+        .bind(kind.to_owned())
+        .bind(resolver)
+        .fetch_optional(connection)
+        .await?;
+
+        Ok(value)
+    }
+
+    async fn store_labels(&self, db: &Database, labels: &[DatabaseEnsLabel]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(labels.len(), DatabaseEnsLabel::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new("UPSERT INTO ens_labels (label, labelhash) ");
+
+            query_builder.push_values(&labels[start..end], |mut row, label| {
+                row.push_bind(label.label.clone())
+                    .push_bind(label.labelhash.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store ens labels into database");
+        }
+
+        Ok(())
+    }
+
+    /// The label and parent of a node never change, existing nodes keep their name.
+    async fn store_names(&self, db: &Database, names: &[DatabaseEnsName]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(names.len(), DatabaseEnsName::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder =
+                QueryBuilder::new("INSERT INTO ens_names (labelhash, node, parent) ");
+
+            query_builder.push_values(&names[start..end], |mut row, name| {
+                row.push_bind(name.labelhash.clone())
+                    .push_bind(name.node.clone())
+                    .push_bind(name.parent.clone());
+            });
+
+            query_builder.push(" ON CONFLICT (node) DO NOTHING");
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store ens names into database");
+        }
+
+        Ok(())
+    }
+
+    async fn store_records(&self, db: &Database, records: &[DatabaseEnsRecord]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(records.len(), DatabaseEnsRecord::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO ens_records (block_number, contract, hash, kind, log_index, node, value) ",
+            );
+
+            query_builder.push_values(&records[start..end], |mut row, record| {
+                row.push_bind(record.block_number)
+                    .push_bind(record.contract.clone())
+                    .push_bind(record.hash.clone())
+                    .push_bind(record.kind.clone())
+                    .push_bind(record.log_index)
+                    .push_bind(record.node.clone())
+                    .push_bind(record.value.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store ens records into database");
+        }
+
+        Ok(())
+    }
+
+    /// Names the nodes with a label or a parent of the batch once both the label and the
+    /// parent name are known. It runs one level at a time so a name and its subnames created
+    /// in the same batch are all filled.
+    async fn fill_names(
+        &self,
+        db: &Database,
+        labelhashes: &[String],
+        nodes: &[String],
+    ) -> Result<()> {
+        let connection = db.get_connection();
+
+        let mut labelhashes: Vec<String> = labelhashes.to_vec();
+
+        labelhashes.sort();
+        labelhashes.dedup();
+
+        let filters = [("labelhash", &labelhashes), ("parent", &nodes.to_vec())];
+
+        for _ in 0..MAX_NAME_DEPTH {
+            let mut filled = 0;
+
+            for (column, values) in filters {
+                for (start, end) in get_chunks(values.len(), 1) {
+                    let mut query_builder = QueryBuilder::new(format!(
+                        "UPDATE ens_names SET name = CASE WHEN parent.name = '' THEN ens_labels.label ELSE concat(ens_labels.label, '.', parent.name) END FROM ens_labels, ens_names AS parent WHERE ens_names.name IS NULL AND ens_names.labelhash = ens_labels.labelhash AND ens_names.parent = parent.node AND parent.name IS NOT NULL AND ens_names.{} IN ",
+                        column
+                    ));
+
+                    query_builder.push_tuples(&values[start..end], |mut row, value| {
+                        row.push_bind(value.clone());
+                    });
+
+                    let result = query_builder
+                        .build()
+                        .execute(connection)
+                        .await
+                        .expect("Unable to update ens names into database");
+
+                    filled += result.rows_affected();
+                }
+            }
+
+            if filled == 0 {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Computes the node of a name, the hash of the parent node and the label hash for each label
+/// from the top level domain.
+pub fn namehash(name: &str) -> String {
+    let mut node = [0u8; 32];
+
+    if !name.is_empty() {
+        for label in name.rsplit('.') {
+            let mut data = node.to_vec();
+
+            data.extend(keccak256(label));
+
+            node = keccak256(data);
+        }
+    }
+
+    format!("0x{}", hex::encode(node))
+}
+
+fn get_subnode(node: &str, labelhash: &str) -> String {
+    let mut data = hex_to_bytes(node);
+
+    data.extend(hex_to_bytes(labelhash));
+
+    format!("0x{}", hex::encode(keccak256(data)))
+}
+
+fn hex_to_bytes(value: &str) -> Vec<u8> {
+    hex::decode(value.trim_start_matches("0x")).unwrap_or_default()
+}
+
+fn decode_address(data: &[u8]) -> Option<String> {
+    match ethabi::decode(&[ParamType::Address], data).ok()?.first() {
+        Some(Token::Address(address)) => Some(format_address(*address)),
+        _ => None,
+    }
+}
+
+/// Decodes the first argument of the data as a string.
+fn decode_string(params: &[ParamType], data: &[u8]) -> Option<String> {
+    match ethabi::decode(params, data).ok()?.into_iter().next() {
+        Some(Token::String(value)) => Some(value),
+        _ => None,
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use crate::db::{
+    db::{get_chunks, Database},
+    models::models::{DatabaseLog, DatabaseLogWithBlock},
+};
+use anyhow::Result;
+use ethabi::{ParamType, Token};
+use ethers::types::U256;
+use field_count::FieldCount;
+use log::info;
+use sqlx::QueryBuilder;
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseErc20Allowance {
+    pub block: i64,
+    pub chain: String,
+    pub log_index: i64,
+    pub owner: String,
+    pub spender: String,
+    pub token: String,
+    pub unlimited: bool,
+    pub value: String,
+}
+
+pub struct ERC20Approvals {}
+
+impl ERC20Approvals {
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseLogWithBlock>> {
+        let connection = db.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseLogWithBlock>(
+            "SELECT logs.*, transactions.block_number FROM logs INNER JOIN transactions ON logs.hash = transactions.hash WHERE logs.erc20_approvals_parsed = false LIMIT 500",
+        )
+        .fetch_all(connection)
+        .await;
+
+        match rows {
+            Ok(logs) => Ok(logs),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn parse(&self, db: &Database, logs: &Vec<DatabaseLogWithBlock>) -> Result<()> {
+        let event = ethabi::Event {
+            name: "Approval".to_owned(),
+            inputs: vec![
+                ethabi::EventParam {
+                    name: "owner".to_owned(),
+                    kind: ParamType::Address,
+                    indexed: true,
+                },
+                ethabi::EventParam {
+                    name: "spender".to_owned(),
+                    kind: ParamType::Address,
+                    indexed: true,
+                },
+                ethabi::EventParam {
+                    name: "value".to_owned(),
+                    kind: ParamType::Uint(256),
+                    indexed: false,
+                },
+            ],
+            anonymous: false,
+        };
+
+        let signature = format!("{:?}", event.signature());
+
+        // Only the latest approval of each (owner, spender, token, chain) is kept.
+        let mut allowances: HashMap<(String, String, String, String), DatabaseErc20Allowance> =
+            HashMap::new();
+
+        let mut db_parsed_logs: Vec<DatabaseLog> = Vec::new();
+
+        for log_with_block in logs {
+            let log = &log_with_block.log;
+
+            db_parsed_logs.push(log.to_owned());
+
+            // Check the first topic against keccak256(Approval(address,address,uint256))
+            if log.topics.len() != 3 || log.topics[0] != Some(signature.clone()) {
+                continue;
+            }
+
+            let raw_log = match log.to_raw_log() {
+                Some(raw_log) => raw_log,
+                None => continue,
+            };
+
+            let decoded = match event.parse_log(raw_log) {
+                Ok(decoded) => decoded,
+                Err(_) => continue,
+            };
+
+            let (owner, spender, value) = match (
+                &decoded.params[0].value,
+                &decoded.params[1].value,
+                &decoded.params[2].value,
+            ) {
+                (Token::Address(owner), Token::Address(spender), Token::Uint(value)) => {
+                    (format!("{:?}", owner), format!("{:?}", spender), *value)
+                }
+                _ => continue,
+            };
+
+            let allowance = DatabaseErc20Allowance {
+                block: log_with_block.block_number,
+                chain: log.chain.clone(),
+                log_index: log.log_index,
+                owner,
+                spender,
+                token: log.address.clone(),
+                unlimited: value == U256::MAX,
+                value: value.to_string(),
+            };
+
+            let id = (
+                allowance.owner.clone(),
+                allowance.spender.clone(),
+                allowance.token.clone(),
+                allowance.chain.clone(),
+            );
+
+            let is_latest = match allowances.get(&id) {
+                Some(stored) => {
+                    (stored.block, stored.log_index) < (allowance.block, allowance.log_index)
+                }
+                None => true,
+            };
+
+            if is_latest {
+                allowances.insert(id, allowance);
+            }
+        }
+
+        let db_allowances: Vec<DatabaseErc20Allowance> = allowances.into_values().collect();
+
+        let connection = db.get_connection();
+
+        if !db_allowances.is_empty() {
+            let chunks = get_chunks(db_allowances.len(), DatabaseErc20Allowance::field_count());
+
+            for (start, end) in chunks {
+                let mut query_builder = QueryBuilder::new(
+                    "INSERT INTO erc20_allowances (block, chain, log_index, owner, spender, token, unlimited, value) ",
+                );
+
+                query_builder.push_values(&db_allowances[start..end], |mut row, allowance| {
+                    row.push_bind(allowance.block)
+                        .push_bind(allowance.chain.clone())
+                        .push_bind(allowance.log_index)
+                        .push_bind(allowance.owner.clone())
+                        .push_bind(allowance.spender.clone())
+                        .push_bind(allowance.token.clone())
+                        .push_bind(allowance.unlimited)
+                        .push_bind(allowance.value.clone());
+                });
+
+                // Logs are not parsed in block order, so an older approval must never
+                // overwrite a newer one.
+                query_builder.push(" ON CONFLICT (owner, spender, token, chain) DO UPDATE SET block = excluded.block, log_index = excluded.log_index, unlimited = excluded.unlimited, value = excluded.value WHERE (erc20_allowances.block, erc20_allowances.log_index) < (excluded.block, excluded.log_index)");
+
+                let query = query_builder.build();
+
+                query
+                    .execute(connection)
+                    .await
+                    .expect("Unable to store erc20 allowances into database");
+            }
+        }
+
+        info!(
+            "ERC20Approvals: Inserted {} erc20 allowances to the database.",
+            db_allowances.len()
+        );
+
+        if !db_parsed_logs.is_empty() {
+            db.update_logs_parsed("erc20_approvals_parsed", &db_parsed_logs)
+                .await
+                .unwrap();
+        }
+
+        Ok(())
+    }
+}
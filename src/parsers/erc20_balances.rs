@@ -11,11 +11,14 @@ use ethers::{
     utils::format_units,
 };
 use field_count::FieldCount;
-use futures::future::join_all;
 use jsonrpsee::tracing::info;
 use sqlx::QueryBuilder;
 
-use super::{erc20_tokens::DatabaseErc20Token, erc20_transfers::DatabaseErc20Transfer};
+use super::{
+    erc20_holders::{BalanceChange, ERC20Holders},
+    erc20_tokens::{DatabaseErc20Token, TOKEN_STATUS_OK},
+    erc20_transfers::DatabaseErc20Transfer,
+};
 
 #[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
 pub struct DatabaseErc20Balance {
@@ -117,6 +120,19 @@ impl ERC20Balances {
 
         let mut balances: HashMap<(String, String, String), DatabaseErc20Balance> = HashMap::new();
 
+        let mut previous_balances: HashMap<(String, String, String), f64> = HashMap::new();
+
+        for balance in stored_balances.iter() {
+            previous_balances.insert(
+                (
+                    balance.address.clone(),
+                    balance.token.clone(),
+                    balance.chain.clone(),
+                ),
+                balance.balance,
+            );
+        }
+
         for balance in stored_balances {
             balances.insert(
                 (
@@ -138,12 +154,10 @@ impl ERC20Balances {
             let sender = transfer.from_address.clone();
 
             let decimals = match tokens_map.get(&(transfer.token.clone(), transfer.chain.clone())) {
+                // Tokens without decimals are retried by the tokens parser.
                 Some(token_data) => match token_data.decimals {
                     Some(decimals) => decimals,
-                    None => {
-                        missing_tokens.insert((transfer.token.clone(), transfer.chain.clone()));
-                        continue;
-                    }
+                    None => continue,
                 },
                 None => {
                     missing_tokens.insert((transfer.token.clone(), transfer.chain.clone()));
@@ -244,6 +258,27 @@ impl ERC20Balances {
                 .expect("Unable to store transactions into database");
         }
 
+        let balance_changes: Vec<BalanceChange> = new_balances
+            .iter()
+            .map(|balance| BalanceChange {
+                address: balance.address.clone(),
+                chain: balance.chain.clone(),
+                token: balance.token.clone(),
+                previous: previous_balances
+                    .get(&(
+                        balance.address.clone(),
+                        balance.token.clone(),
+                        balance.chain.clone(),
+                    ))
+                    .copied()
+                    .unwrap_or_default(),
+                balance: balance.balance,
+            })
+            .filter(|change| change.previous != change.balance)
+            .collect();
+
+        ERC20Holders {}.update(db, &balance_changes).await?;
+
         if parsed_transfers.len() > 0 {
             let chunks = get_chunks(parsed_transfers.len(), DatabaseErc20Transfer::field_count());
 
@@ -275,7 +310,7 @@ impl ERC20Balances {
 
         info!("ERC20Balances: Inserted {} balances", total_new_balances);
 
-        if missing_tokens.len() > 0 {
+        if !missing_tokens.is_empty() {
             let erc20_tokens = ERC20Tokens {};
 
             info!(
@@ -287,92 +322,21 @@ impl ERC20Balances {
                 .into_iter()
                 .collect::<Vec<(String, String)>>();
 
-            let chunks = missing_tokens_vector.chunks(200);
-
-            for chunk in chunks {
-                let mut works = vec![];
-                for (token, chain) in chunk {
-                    works
-                        .push(erc20_tokens.get_token_metadata((token.to_owned(), chain.to_owned())))
-                }
-
-                let result = join_all(works).await;
-
-                let tokens: Vec<DatabaseErc20Token> = result
-                    .into_iter()
-                    .filter(|token| token.is_some())
-                    .map(|token| token.unwrap())
-                    .collect();
-
-                let tokens_amount = tokens.len();
-
-                let mut query_builder = QueryBuilder::new(
-                    "UPSERT INTO erc20_tokens (address, chain, decimals, name, symbol) ",
-                );
-
-                let mut tokens_data = vec![];
-
-                for token in tokens {
-                    let name = match token.name {
-                        Some(name) => {
-                            let name_fixed: String = name.replace("'", "");
-
-                            let name_bytes = name_fixed.as_bytes();
-
-                            let name_parsed = String::from_utf8_lossy(name_bytes);
-
-                            format!("'{}'", name_parsed)
-                        }
-                        None => String::from("NULL"),
-                    };
-
-                    let symbol = match token.symbol {
-                        Some(symbol) => {
-                            let symbol_fixed: String = symbol.replace("'", "");
-
-                            let symbol_bytes = symbol_fixed.as_bytes();
-
-                            let symbol_parsed = String::from_utf8_lossy(symbol_bytes);
-
-                            format!("'{}'", symbol_parsed)
-                        }
-                        None => String::from("NULL"),
-                    };
-
-                    tokens_data.push((
-                        token.address,
-                        token.chain,
-                        token.decimals.unwrap(),
-                        name,
-                        symbol,
-                    ));
-                }
-
-                query_builder.push_values(
-                    &tokens_data,
-                    |mut row, (address, chain, decimals, name, symbol)| {
-                        row.push_bind(address)
-                            .push_bind(chain)
-                            .push_bind(decimals)
-                            .push_bind(name)
-                            .push_bind(symbol);
-                    },
-                );
-
-                if tokens_amount > 0 {
-                    let query = query_builder.build();
-
-                    query
-                        .execute(connection)
-                        .await
-                        .expect("Unable to store erc20 tokens into database");
-                }
+            let tokens = erc20_tokens
+                .get_tokens_metadata(&missing_tokens_vector)
+                .await;
 
-                info!(
-                    "ERC20Balances: Inserted {} missing tokens data",
-                    tokens_amount
-                );
+            if !tokens.is_empty() {
+                erc20_tokens.store_tokens(db, &tokens).await?;
             }
+
+            info!(
+                "ERC20Balances: Inserted {} missing tokens data",
+                tokens
+                    .iter()
+                    .filter(|token| token.status == TOKEN_STATUS_OK)
+                    .count()
+            );
         }
         Ok(())
     }
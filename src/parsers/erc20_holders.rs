@@ -0,0 +1,392 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::db::db::{get_chunks, Database};
+use anyhow::Result;
+use field_count::FieldCount;
+use log::info;
+use sqlx::QueryBuilder;
+
+/// Addresses used to burn tokens, they are never counted as holders. The holders backfill in
+/// the erc20 holders migration excludes the same addresses.
+pub const BURN_ADDRESSES: &[&str] = &[
+    "0x0000000000000000000000000000000000000000",
+    "0x0000000000000000000000000000000000000001",
+    "0x000000000000000000000000000000000000dead",
+    "0xdead000000000000000042069420694206942069",
+];
+
+/// Amount of holders materialized for each token, also used by the holders migration.
+pub const TOP_HOLDERS_AMOUNT: usize = 100;
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseErc20TopHolder {
+    pub address: String,
+    pub balance: f64,
+    pub chain: String,
+    pub token: String,
+}
+
+/// Balance of an address before and after a batch of transfers.
+#[derive(Debug, Clone)]
+pub struct BalanceChange {
+    pub address: String,
+    pub chain: String,
+    pub token: String,
+    pub previous: f64,
+    pub balance: f64,
+}
+
+/// Holders statistics maintained by the balances parser on each batch of updated balances.
+pub struct ERC20Holders {}
+
+impl ERC20Holders {
+    pub async fn update(&self, db: &Database, changes: &[BalanceChange]) -> Result<()> {
+        let burn_addresses: HashSet<&str> = BURN_ADDRESSES.iter().copied().collect();
+
+        let changes: Vec<&BalanceChange> = changes
+            .iter()
+            .filter(|change| !burn_addresses.contains(change.address.as_str()))
+            .collect();
+
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        self.update_holders_count(db, &changes).await?;
+
+        self.update_top_holders(db, &changes).await?;
+
+        Ok(())
+    }
+
+    async fn update_holders_count(&self, db: &Database, changes: &[&BalanceChange]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let mut counts: HashMap<(String, String), i64> = HashMap::new();
+
+        for change in changes {
+            let delta = match (change.previous > 0.0, change.balance > 0.0) {
+                (false, true) => 1,
+                (true, false) => -1,
+                _ => continue,
+            };
+
+            *counts
+                .entry((change.token.clone(), change.chain.clone()))
+                .or_default() += delta;
+        }
+
+        let counts: Vec<(String, String, i64)> = counts
+            .into_iter()
+            .filter(|(_, delta)| *delta != 0)
+            .map(|((token, chain), delta)| (token, chain, delta))
+            .collect();
+
+        if counts.is_empty() {
+            return Ok(());
+        }
+
+        let chunks = get_chunks(counts.len(), 3);
+
+        for (start, end) in chunks {
+            let mut query_builder =
+                QueryBuilder::new("INSERT INTO erc20_holders (token, chain, holders) ");
+
+            query_builder.push_values(&counts[start..end], |mut row, (token, chain, delta)| {
+                row.push_bind(token.clone())
+                    .push_bind(chain.clone())
+                    .push_bind(*delta);
+            });
+
+            query_builder.push(" ON CONFLICT (token, chain) DO UPDATE SET holders = erc20_holders.holders + excluded.holders");
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store erc20 holders into database");
+        }
+
+        info!(
+            "ERC20Holders: Updated holders count of {} tokens.",
+            counts.len()
+        );
+
+        Ok(())
+    }
+
+    /// The top holders of each token are merged with the updated balances. When a balance of a
+    /// top holder decreases, an address outside of the list could take its place, so the list
+    /// is computed again from the balances.
+    async fn update_top_holders(&self, db: &Database, changes: &[&BalanceChange]) -> Result<()> {
+        let tokens: Vec<(String, String)> = changes
+            .iter()
+            .map(|change| (change.token.clone(), change.chain.clone()))
+            .collect::<HashSet<(String, String)>>()
+            .into_iter()
+            .collect();
+
+        let mut top_holders: HashMap<(String, String), HashMap<String, f64>> = HashMap::new();
+
+        for holder in self.get_top_holders(db, &tokens).await? {
+            top_holders
+                .entry((holder.token, holder.chain))
+                .or_default()
+                .insert(holder.address, holder.balance);
+        }
+
+        let mut changes_by_token: HashMap<(String, String), Vec<&BalanceChange>> = HashMap::new();
+
+        for change in changes {
+            changes_by_token
+                .entry((change.token.clone(), change.chain.clone()))
+                .or_default()
+                .push(change);
+        }
+
+        let mut db_top_holders: Vec<DatabaseErc20TopHolder> = Vec::new();
+
+        let mut db_removed_holders: Vec<(String, String, String)> = Vec::new();
+
+        let mut recompute: Vec<(String, String)> = Vec::new();
+
+        for ((token, chain), token_changes) in changes_by_token {
+            let mut holders = top_holders
+                .remove(&(token.clone(), chain.clone()))
+                .unwrap_or_default();
+
+            let decreased = token_changes
+                .iter()
+                .any(|change| match holders.get(&change.address) {
+                    Some(balance) => change.balance < *balance,
+                    None => false,
+                });
+
+            if decreased {
+                recompute.push((token, chain));
+                continue;
+            }
+
+            let previous_holders: HashSet<String> = holders.keys().cloned().collect();
+
+            for change in token_changes.iter().filter(|change| change.balance > 0.0) {
+                holders.insert(change.address.clone(), change.balance);
+            }
+
+            let mut sorted: Vec<(String, f64)> = holders.into_iter().collect();
+
+            sorted.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+            sorted.truncate(TOP_HOLDERS_AMOUNT);
+
+            let current_holders: HashSet<String> =
+                sorted.iter().map(|(address, _)| address.clone()).collect();
+
+            for address in previous_holders.difference(&current_holders) {
+                db_removed_holders.push((address.clone(), token.clone(), chain.clone()));
+            }
+
+            let changed: HashSet<&String> =
+                token_changes.iter().map(|change| &change.address).collect();
+
+            for (address, balance) in sorted {
+                if changed.contains(&address) {
+                    db_top_holders.push(DatabaseErc20TopHolder {
+                        address,
+                        balance,
+                        chain: chain.clone(),
+                        token: token.clone(),
+                    });
+                }
+            }
+        }
+
+        if !recompute.is_empty() {
+            self.delete_top_holders(db, &recompute).await?;
+
+            for (token, chain) in recompute.iter() {
+                let mut holders = self.get_largest_balances(db, token, chain).await?;
+
+                db_top_holders.append(&mut holders);
+            }
+        }
+
+        if !db_removed_holders.is_empty() {
+            self.remove_top_holders(db, &db_removed_holders).await?;
+        }
+
+        if !db_top_holders.is_empty() {
+            self.store_top_holders(db, &db_top_holders).await?;
+        }
+
+        info!(
+            "ERC20Holders: Updated {} top holders and computed again {} tokens top holders.",
+            db_top_holders.len(),
+            recompute.len()
+        );
+
+        Ok(())
+    }
+
+    pub async fn get_top_holders(
+        &self,
+        db: &Database,
+        tokens: &[(String, String)],
+    ) -> Result<Vec<DatabaseErc20TopHolder>> {
+        let connection = db.get_connection();
+
+        let mut db_holders = Vec::new();
+
+        if tokens.is_empty() {
+            return Ok(db_holders);
+        }
+
+        let chunks = get_chunks(tokens.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder =
+                QueryBuilder::new("SELECT * FROM erc20_top_holders WHERE (token, chain) IN ");
+
+            query_builder.push_tuples(&tokens[start..end], |mut row, (token, chain)| {
+                row.push_bind(token.clone()).push_bind(chain.clone());
+            });
+
+            let rows = query_builder
+                .build_query_as::<DatabaseErc20TopHolder>()
+                .fetch_all(connection)
+                .await;
+
+            match rows {
+                Ok(mut rows) => db_holders.append(&mut rows),
+                Err(_) => continue,
+            }
+        }
+
+        Ok(db_holders)
+    }
+
+    async fn get_largest_balances(
+        &self,
+        db: &Database,
+        token: &str,
+        chain: &str,
+    ) -> Result<Vec<DatabaseErc20TopHolder>> {
+        let connection = db.get_connection();
+
+        let mut query_builder = QueryBuilder::new(
+            "SELECT address, balance, chain, token FROM erc20_balances WHERE token = ",
+        );
+
+        query_builder
+            .push_bind(token)
+            .push(" AND chain = ")
+            .push_bind(chain)
+            .push(" AND balance > 0 AND address NOT IN ");
+
+        query_builder.push_tuples(BURN_ADDRESSES, |mut row, address| {
+            row.push_bind(*address);
+        });
+
+        query_builder
+            .push(" ORDER BY balance DESC LIMIT ")
+            .push_bind(TOP_HOLDERS_AMOUNT as i64);
+
+        let rows = query_builder
+            .build_query_as::<DatabaseErc20TopHolder>()
+            .fetch_all(connection)
+            .await;
+
+        match rows {
+            Ok(rows) => Ok(rows),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    async fn store_top_holders(
+        &self,
+        db: &Database,
+        holders: &[DatabaseErc20TopHolder],
+    ) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(holders.len(), DatabaseErc20TopHolder::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO erc20_top_holders (address, balance, chain, token) ",
+            );
+
+            query_builder.push_values(&holders[start..end], |mut row, holder| {
+                row.push_bind(holder.address.clone())
+                    .push_bind(holder.balance)
+                    .push_bind(holder.chain.clone())
+                    .push_bind(holder.token.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store erc20 top holders into database");
+        }
+
+        Ok(())
+    }
+
+    async fn remove_top_holders(
+        &self,
+        db: &Database,
+        holders: &[(String, String, String)],
+    ) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(holders.len(), 3);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "DELETE FROM erc20_top_holders WHERE (address, token, chain) IN ",
+            );
+
+            query_builder.push_tuples(&holders[start..end], |mut row, (address, token, chain)| {
+                row.push_bind(address.clone())
+                    .push_bind(token.clone())
+                    .push_bind(chain.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to delete erc20 top holders from database");
+        }
+
+        Ok(())
+    }
+
+    async fn delete_top_holders(&self, db: &Database, tokens: &[(String, String)]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(tokens.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder =
+                QueryBuilder::new("DELETE FROM erc20_top_holders WHERE (token, chain) IN ");
+
+            query_builder.push_tuples(&tokens[start..end], |mut row, (token, chain)| {
+                row.push_bind(token.clone()).push_bind(chain.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to delete erc20 top holders from database");
+        }
+
+        Ok(())
+    }
+}
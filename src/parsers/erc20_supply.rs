@@ -0,0 +1,397 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    chains::chains::get_chain,
+    db::db::{get_chunks, Database},
+    utils::{format_address, format_number},
+};
+use anyhow::Result;
+use ethabi::Address;
+use ethers::{
+    providers::{Http, Middleware, Provider},
+    types::{H160, U256},
+};
+use field_count::FieldCount;
+use futures::future::join_all;
+use log::info;
+use rand::seq::SliceRandom;
+use sqlx::{Postgres, QueryBuilder, Transaction};
+
+use super::{erc20_tokens::ERC20, erc20_transfers::DatabaseErc20Transfer};
+
+/// Amount of tokens reconciled against `totalSupply()` on each iteration.
+pub const RECONCILE_BATCH_SIZE: i64 = 100;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DatabaseErc20TransferWithBlock {
+    #[sqlx(flatten)]
+    pub transfer: DatabaseErc20Transfer,
+    pub block_number: i64,
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseErc20SupplyChange {
+    pub block_number: i64,
+    pub burned: String,
+    pub chain: String,
+    pub minted: String,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseErc20OnchainSupply {
+    pub chain: String,
+    pub onchain_block: i64,
+    pub onchain_supply: String,
+    pub token: String,
+}
+
+pub struct ERC20Supply {}
+
+impl ERC20Supply {
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseErc20TransferWithBlock>> {
+        let connection = db.get_connection();
+
+        let zero_address = format_address(H160::zero());
+
+        let rows = sqlx::query_as::<_, DatabaseErc20TransferWithBlock>(
+            "SELECT erc20_transfers.*, transactions.block_number FROM erc20_transfers INNER JOIN transactions ON erc20_transfers.hash = transactions.hash WHERE erc20_transfers.erc20_supply_parsed = false AND (erc20_transfers.from_address = $1 OR erc20_transfers.to_address = $1) LIMIT 500",
+        )
+        .bind(zero_address)
+        .fetch_all(connection)
+        .await;
+
+        match rows {
+            Ok(transfers) => Ok(transfers),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn parse(
+        &self,
+        db: &Database,
+        transfers: &[DatabaseErc20TransferWithBlock],
+    ) -> Result<()> {
+        let zero_address = format_address(H160::zero());
+
+        // Minted and burned amounts aggregated by (token, chain, block).
+        let mut changes: HashMap<(String, String, i64), (U256, U256)> = HashMap::new();
+
+        for transfer_with_block in transfers {
+            let transfer = &transfer_with_block.transfer;
+
+            // Transfers between the zero address don't change the supply.
+            if transfer.from_address == transfer.to_address {
+                continue;
+            }
+
+            let value = match U256::from_dec_str(&transfer.value) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+
+            let (minted, burned) = changes
+                .entry((
+                    transfer.token.clone(),
+                    transfer.chain.clone(),
+                    transfer_with_block.block_number,
+                ))
+                .or_default();
+
+            if transfer.from_address == zero_address {
+                *minted = minted.saturating_add(value);
+            } else {
+                *burned = burned.saturating_add(value);
+            }
+        }
+
+        let db_changes: Vec<DatabaseErc20SupplyChange> = changes
+            .into_iter()
+            .map(
+                |((token, chain, block_number), (minted, burned))| DatabaseErc20SupplyChange {
+                    block_number,
+                    burned: format_number(burned),
+                    chain,
+                    minted: format_number(minted),
+                    token,
+                },
+            )
+            .collect();
+
+        // The supply is additive, so the changes and the parsed transfers are committed
+        // together to never aggregate the same transfers twice.
+        let mut transaction = db.get_connection().begin().await?;
+
+        if !db_changes.is_empty() {
+            self.store_changes(&mut transaction, &db_changes).await?;
+        }
+
+        if !transfers.is_empty() {
+            self.update_transfers_parsed(&mut transaction, transfers)
+                .await?;
+        }
+
+        transaction
+            .commit()
+            .await
+            .expect("Unable to store erc20 supply changes into database");
+
+        info!(
+            "ERC20Supply: Inserted {} supply changes from {} transfers to the database.",
+            db_changes.len(),
+            transfers.len()
+        );
+
+        Ok(())
+    }
+
+    /// Stores the supply changes of each block and adds them to the tokens total supply. Both
+    /// tables are additive, so transfers must only be aggregated once.
+    async fn store_changes(
+        &self,
+        transaction: &mut Transaction<'_, Postgres>,
+        changes: &[DatabaseErc20SupplyChange],
+    ) -> Result<()> {
+        let chunks = get_chunks(changes.len(), DatabaseErc20SupplyChange::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "INSERT INTO erc20_supply_changes (block_number, burned, chain, minted, token) ",
+            );
+
+            query_builder.push_values(&changes[start..end], |mut row, change| {
+                row.push_bind(change.block_number)
+                    .push_bind(change.burned.clone())
+                    .push_unseparated("::DECIMAL")
+                    .push_bind(change.chain.clone())
+                    .push_bind(change.minted.clone())
+                    .push_unseparated("::DECIMAL")
+                    .push_bind(change.token.clone());
+            });
+
+            query_builder.push(" ON CONFLICT (token, chain, block_number) DO UPDATE SET burned = erc20_supply_changes.burned + excluded.burned, minted = erc20_supply_changes.minted + excluded.minted");
+
+            let query = query_builder.build();
+
+            query
+                .execute(&mut *transaction)
+                .await
+                .expect("Unable to store erc20 supply changes into database");
+        }
+
+        let mut supplies: HashMap<(String, String), (i64, Vec<&DatabaseErc20SupplyChange>)> =
+            HashMap::new();
+
+        for change in changes {
+            let (last_block, token_changes) = supplies
+                .entry((change.token.clone(), change.chain.clone()))
+                .or_default();
+
+            *last_block = (*last_block).max(change.block_number);
+
+            token_changes.push(change);
+        }
+
+        let supplies: Vec<(String, String, i64, String, String)> = supplies
+            .into_iter()
+            .map(|((token, chain), (last_block, token_changes))| {
+                let (minted, burned) = token_changes.iter().fold(
+                    (U256::zero(), U256::zero()),
+                    |(minted, burned), change| {
+                        (
+                            minted.saturating_add(U256::from_dec_str(&change.minted).unwrap()),
+                            burned.saturating_add(U256::from_dec_str(&change.burned).unwrap()),
+                        )
+                    },
+                );
+
+                (
+                    token,
+                    chain,
+                    last_block,
+                    format_number(minted),
+                    format_number(burned),
+                )
+            })
+            .collect();
+
+        let chunks = get_chunks(supplies.len(), 5);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "INSERT INTO erc20_supply (token, chain, last_block, minted, burned) ",
+            );
+
+            query_builder.push_values(
+                &supplies[start..end],
+                |mut row, (token, chain, last_block, minted, burned)| {
+                    row.push_bind(token.clone())
+                        .push_bind(chain.clone())
+                        .push_bind(*last_block)
+                        .push_bind(minted.clone())
+                        .push_unseparated("::DECIMAL")
+                        .push_bind(burned.clone())
+                        .push_unseparated("::DECIMAL");
+                },
+            );
+
+            query_builder.push(" ON CONFLICT (token, chain) DO UPDATE SET last_block = greatest(erc20_supply.last_block, excluded.last_block), minted = erc20_supply.minted + excluded.minted, burned = erc20_supply.burned + excluded.burned");
+
+            let query = query_builder.build();
+
+            query
+                .execute(&mut *transaction)
+                .await
+                .expect("Unable to store erc20 supply into database");
+        }
+
+        Ok(())
+    }
+
+    async fn update_transfers_parsed(
+        &self,
+        transaction: &mut Transaction<'_, Postgres>,
+        transfers: &[DatabaseErc20TransferWithBlock],
+    ) -> Result<()> {
+        let chunks = get_chunks(transfers.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPDATE erc20_transfers SET erc20_supply_parsed = true WHERE (hash, log_index) IN ",
+            );
+
+            query_builder.push_tuples(&transfers[start..end], |mut row, transfer_with_block| {
+                row.push_bind(transfer_with_block.transfer.hash.clone())
+                    .push_bind(transfer_with_block.transfer.log_index);
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(&mut *transaction)
+                .await
+                .expect("Unable to update erc20 transfers into database");
+        }
+
+        Ok(())
+    }
+
+    /// Compares the supply of the tokens not reconciled in the last hours with the value
+    /// returned by `totalSupply()`. Tokens minted without a transfer from the zero address
+    /// (e.g. rebasing tokens) will differ from the supply derived from transfers.
+    pub async fn reconcile(&self, db: &Database) -> Result<()> {
+        let connection = db.get_connection();
+
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT token, chain FROM erc20_supply WHERE reconciled_at IS NULL OR reconciled_at < now() - INTERVAL '6 hours' ORDER BY reconciled_at NULLS FIRST LIMIT $1",
+        )
+        .bind(RECONCILE_BATCH_SIZE)
+        .fetch_all(connection)
+        .await;
+
+        let tokens = match rows {
+            Ok(tokens) => tokens,
+            Err(_) => return Ok(()),
+        };
+
+        if tokens.is_empty() {
+            return Ok(());
+        }
+
+        let mut work = vec![];
+
+        for (token, chain) in tokens.iter() {
+            work.push(self.get_onchain_supply(token.clone(), chain.clone()))
+        }
+
+        let onchain_supplies: Vec<DatabaseErc20OnchainSupply> =
+            join_all(work).await.into_iter().flatten().collect();
+
+        if !onchain_supplies.is_empty() {
+            let chunks = get_chunks(
+                onchain_supplies.len(),
+                DatabaseErc20OnchainSupply::field_count(),
+            );
+
+            for (start, end) in chunks {
+                let mut query_builder = QueryBuilder::new(
+                    "UPDATE erc20_supply SET onchain_block = data.onchain_block, onchain_supply = data.onchain_supply::DECIMAL FROM (",
+                );
+
+                query_builder.push_values(&onchain_supplies[start..end], |mut row, supply| {
+                    row.push_bind(supply.chain.clone())
+                        .push_bind(supply.onchain_block)
+                        .push_bind(supply.onchain_supply.clone())
+                        .push_bind(supply.token.clone());
+                });
+
+                query_builder.push(") AS data (chain, onchain_block, onchain_supply, token) WHERE erc20_supply.token = data.token AND erc20_supply.chain = data.chain");
+
+                let query = query_builder.build();
+
+                query
+                    .execute(connection)
+                    .await
+                    .expect("Unable to store erc20 onchain supply into database");
+            }
+        }
+
+        // Tokens failing the call are also marked to avoid retrying them on every iteration.
+        let chunks = get_chunks(tokens.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPDATE erc20_supply SET reconciled_at = now() WHERE (token, chain) IN ",
+            );
+
+            query_builder.push_tuples(&tokens[start..end], |mut row, (token, chain)| {
+                row.push_bind(token.clone()).push_bind(chain.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to update erc20 supply into database");
+        }
+
+        info!(
+            "ERC20Supply: Reconciled {} of {} tokens supply with totalSupply().",
+            onchain_supplies.len(),
+            tokens.len()
+        );
+
+        Ok(())
+    }
+
+    pub async fn get_onchain_supply(
+        &self,
+        address: String,
+        chain: String,
+    ) -> Option<DatabaseErc20OnchainSupply> {
+        let rpcs = get_chain(chain.to_string()).get_rpcs();
+
+        let rpc = rpcs.choose(&mut rand::thread_rng())?;
+
+        let provider = match Provider::<Http>::try_from(rpc.as_str()) {
+            Ok(provider) => provider,
+            Err(_) => return None,
+        };
+
+        let client = Arc::new(provider);
+
+        let block = client.get_block_number().await.ok()?;
+
+        let token = ERC20::new(address.parse::<Address>().ok()?, Arc::clone(&client));
+
+        let supply = token.total_supply().block(block).call().await.ok()?;
+
+        Some(DatabaseErc20OnchainSupply {
+            chain,
+            onchain_block: block.as_u64() as i64,
+            onchain_supply: format_number(supply),
+            token: address,
+        })
+    }
+}
@@ -1,24 +1,45 @@
-use std::{collections::HashSet, sync::Arc};
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     chains::chains::{get_chain, get_chains},
     db::db::{get_chunks, Database},
+    rpc::multicall::{aggregate3, is_execution_error, Call},
 };
 use anyhow::Result;
-use ethabi::Address;
+use ethabi::{Address, ParamType, Token};
 use ethers::{
     prelude::abigen,
     providers::{Http, Provider},
+    utils::id,
 };
 use field_count::FieldCount;
 use futures::future::join_all;
 use log::info;
+use rand::seq::SliceRandom;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use sqlx::QueryBuilder;
 
 use super::erc20_transfers::DatabaseErc20Transfer;
 
+/// Metadata was fetched, only tokens with this status have decimals.
+pub const TOKEN_STATUS_OK: &str = "ok";
+
+/// The contract doesn't implement `decimals()`, it is not fetched again.
+pub const TOKEN_STATUS_NOT_A_TOKEN: &str = "not-a-token";
+
+/// The RPC failed, the token is fetched again after an increasing delay.
+pub const TOKEN_STATUS_RETRY_LATER: &str = "retry-later";
+
+/// Tokens included in a curated tokens list.
+pub const TOKEN_TRUST_CURATED: &str = "curated";
+
+/// Tokens only discovered from transfers.
+pub const TOKEN_TRUST_UNVERIFIED: &str = "unverified";
+
+/// Amount of tokens fetched on each `aggregate3` call, each token uses three calls.
+pub const METADATA_BATCH_SIZE: usize = 100;
+
 #[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
 pub struct DatabaseErc20Token {
     pub address: String,
@@ -26,6 +47,11 @@ pub struct DatabaseErc20Token {
     pub name: Option<String>,
     pub decimals: Option<i64>,
     pub symbol: Option<String>,
+    pub status: String,
+    pub logo_uri: Option<String>,
+    pub source: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub trust: String,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -34,6 +60,39 @@ pub struct TokenListData {
     pub name: String,
     pub address: String,
     pub decimals: i64,
+    #[serde(rename = "chainId", default)]
+    pub chain_id: Option<i64>,
+    #[serde(rename = "logoURI", default)]
+    pub logo_uri: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// List following the Token List standard (https://tokenlists.org).
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenList {
+    pub name: String,
+    pub tokens: Vec<TokenListData>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TokenListFormat {
+    Standard(TokenList),
+    Array(Vec<TokenListData>),
+}
+
+/// Tokens list to ingest, lists without a chain use the `chainId` of each token.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenListSource {
+    pub chain: Option<String>,
+    pub source: String,
+    #[serde(default = "default_trust")]
+    pub trust: String,
+}
+
+fn default_trust() -> String {
+    TOKEN_TRUST_CURATED.to_owned()
 }
 
 pub struct ERC20Tokens {}
@@ -44,15 +103,18 @@ abigen!(
         function name() external view returns (string)
         function symbol() external view returns (string)
         function decimals() external view returns (uint8)
+        function totalSupply() external view returns (uint256)
     ]"#,
 );
 
 impl ERC20Tokens {
+    /// Transfers of unknown tokens, skipping the emitters already classified by their
+    /// bytecode as neither an ERC20 nor a proxy.
     pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseErc20Transfer>> {
         let connection = db.get_connection();
 
         let rows = sqlx::query_as::<_, DatabaseErc20Transfer>(
-            "SELECT * FROM erc20_transfers transfer WHERE NOT EXISTS (SELECT 1 FROM erc20_tokens token WHERE transfer.chain = token.chain AND transfer.token = token.address) LIMIT 500",
+            "SELECT * FROM erc20_transfers transfer WHERE NOT EXISTS (SELECT 1 FROM erc20_tokens token WHERE transfer.chain = token.chain AND transfer.token = token.address) AND NOT EXISTS (SELECT 1 FROM contracts contract WHERE transfer.chain = contract.chain AND transfer.token = contract.contract AND contract.code_parsed = true AND NOT ('erc20' = ANY(contract.standards) OR 'proxy' = ANY(contract.standards))) LIMIT 500",
         )
         .fetch_all(connection)
         .await;
@@ -63,203 +125,142 @@ impl ERC20Tokens {
         }
     }
 
-    pub async fn parse_extenal(&self, db: &Database) -> Result<()> {
-        let chains = get_chains();
-
-        for (name, chain) in chains {
-            info!("ERC20Tokens: fetching extenal tokens for chain {}", name);
-
-            for url in chain.tokens_lists {
-                let client = Client::new();
-                let response = client.get(&**url).send().await;
-
-                let mut tokens = vec![];
-
-                match response {
-                    Ok(data) => match data.text().await {
-                        Ok(response) => {
-                            let tokens_list = serde_json::from_str::<Vec<TokenListData>>(&response);
-                            match tokens_list {
-                                Ok(tokens_list) => {
-                                    for token in tokens_list {
-                                        let db_token = DatabaseErc20Token {
-                                            address: token.address,
-                                            chain: name.clone(),
-                                            name: Some(token.name),
-                                            decimals: Some(token.decimals),
-                                            symbol: Some(token.symbol),
-                                        };
-
-                                        tokens.push(db_token);
-                                    }
-                                }
-                                Err(_) => continue,
-                            }
-                        }
-                        Err(_) => continue,
-                    },
-                    Err(_) => continue,
-                }
+    /// Returns the tokens lists of every chain followed by the lists configured in the JSON
+    /// file, each entry being an URL or a local path.
+    pub fn load_tokens_lists(path: &Option<String>) -> Vec<TokenListSource> {
+        let mut sources: Vec<TokenListSource> = get_chains()
+            .into_iter()
+            .flat_map(|(name, chain)| {
+                chain.tokens_lists.iter().map(move |url| TokenListSource {
+                    chain: Some(name.clone()),
+                    source: url.to_string(),
+                    trust: TOKEN_TRUST_CURATED.to_owned(),
+                })
+            })
+            .collect();
 
-                let connection = db.get_connection();
+        if let Some(path) = path {
+            let file = std::fs::read_to_string(path).expect("Unable to read tokens lists file.");
 
-                let mut query_builder = QueryBuilder::new(
-                    "UPSERT INTO erc20_tokens (address, chain, decimals, name, symbol) ",
-                );
+            let configured: Vec<TokenListSource> =
+                serde_json::from_str(&file).expect("Unable to parse tokens lists file.");
 
-                let tokens_amount = tokens.len();
+            sources.extend(configured);
+        }
 
-                let mut tokens_data = vec![];
+        sources
+    }
 
-                for token in tokens {
-                    let name = match token.name {
-                        Some(name) => {
-                            let name_fixed: String = name.replace("'", "");
+    pub async fn parse_extenal(&self, db: &Database, sources: &[TokenListSource]) -> Result<()> {
+        let chains_by_id: HashMap<i64, String> = get_chains()
+            .into_iter()
+            .map(|(name, chain)| (chain.id, name))
+            .collect();
 
-                            let name_bytes = name_fixed.as_bytes();
+        // Tokens included in more than one list keep the data of the first list.
+        let mut tokens: HashMap<(String, String), DatabaseErc20Token> = HashMap::new();
 
-                            let name_parsed = String::from_utf8_lossy(name_bytes);
+        for source in sources {
+            let tokens_list = match self.get_tokens_list(&source.source).await {
+                Some(tokens_list) => tokens_list,
+                None => {
+                    info!("ERC20Tokens: unable to fetch tokens list {}", source.source);
+                    continue;
+                }
+            };
 
-                            format!("'{}'", name_parsed)
-                        }
-                        None => String::from("NULL"),
-                    };
+            let mut tokens_amount = 0;
 
-                    let symbol = match token.symbol {
-                        Some(symbol) => {
-                            let symbol_fixed: String = symbol.replace("'", "");
+            for token in tokens_list {
+                // Lists without a chain only include tokens with a known chainId, lists of a
+                // chain skip tokens of other chains.
+                let chain = match (&source.chain, token.chain_id) {
+                    (Some(chain), None) => chain.clone(),
+                    (Some(chain), Some(chain_id)) => match chains_by_id.get(&chain_id) {
+                        Some(token_chain) if token_chain == chain => chain.clone(),
+                        _ => continue,
+                    },
+                    (None, Some(chain_id)) => match chains_by_id.get(&chain_id) {
+                        Some(chain) => chain.clone(),
+                        None => continue,
+                    },
+                    (None, None) => continue,
+                };
+
+                let address = token.address.to_lowercase();
+
+                tokens_amount += 1;
+
+                tokens
+                    .entry((address.clone(), chain.clone()))
+                    .or_insert(DatabaseErc20Token {
+                        address,
+                        chain,
+                        name: Some(token.name),
+                        decimals: Some(token.decimals),
+                        symbol: Some(token.symbol),
+                        status: TOKEN_STATUS_OK.to_owned(),
+                        logo_uri: token.logo_uri,
+                        source: Some(source.source.clone()),
+                        tags: Some(token.tags),
+                        trust: source.trust.clone(),
+                    });
+            }
 
-                            let symbol_bytes = symbol_fixed.as_bytes();
+            info!(
+                "ERC20Tokens: fetched {} tokens from list {}",
+                tokens_amount, source.source
+            );
+        }
 
-                            let symbol_parsed = String::from_utf8_lossy(symbol_bytes);
+        let tokens: Vec<DatabaseErc20Token> = tokens.into_values().collect();
 
-                            format!("'{}'", symbol_parsed)
-                        }
-                        None => String::from("NULL"),
-                    };
+        if !tokens.is_empty() {
+            self.store_tokens(db, &tokens).await?;
+        }
 
-                    tokens_data.push((
-                        token.address,
-                        token.chain,
-                        token.decimals.unwrap(),
-                        name,
-                        symbol,
-                    ));
-                }
+        info!(
+            "ERC20Tokens: inserted {} tokens from {} lists",
+            tokens.len(),
+            sources.len()
+        );
 
-                query_builder.push_values(
-                    &tokens_data,
-                    |mut row, (address, chain, decimals, name, symbol)| {
-                        row.push_bind(address)
-                            .push_bind(chain)
-                            .push_bind(decimals)
-                            .push_bind(name)
-                            .push_bind(symbol);
-                    },
-                );
+        Ok(())
+    }
 
-                if tokens_amount > 0 {
-                    let query = query_builder.build();
+    /// Reads a tokens list from an URL or a local file, both in the Token List standard format
+    /// or as a plain array of tokens.
+    async fn get_tokens_list(&self, source: &str) -> Option<Vec<TokenListData>> {
+        let response = if source.starts_with("http://") || source.starts_with("https://") {
+            let client = Client::new();
 
-                    query
-                        .execute(connection)
-                        .await
-                        .expect("Unable to store erc20 balances into database");
-                }
+            client.get(source).send().await.ok()?.text().await.ok()?
+        } else {
+            std::fs::read_to_string(source).ok()?
+        };
 
-                info!(
-                    "ERC20Tokens: inserted {} tokens for chain {}",
-                    tokens_amount, name
-                );
-            }
+        match serde_json::from_str::<TokenListFormat>(&response).ok()? {
+            TokenListFormat::Standard(tokens_list) => Some(tokens_list.tokens),
+            TokenListFormat::Array(tokens) => Some(tokens),
         }
-        Ok(())
     }
 
-    pub async fn parse(&self, db: &Database, transfers: &Vec<DatabaseErc20Transfer>) -> Result<()> {
+    pub async fn parse(&self, db: &Database, transfers: &[DatabaseErc20Transfer]) -> Result<()> {
         let connection = db.get_connection();
 
         let unique_tokens: Vec<(String, String)> = transfers
-            .into_iter()
+            .iter()
             .map(|token| (token.token.clone(), token.chain.clone()))
             .collect::<HashSet<(String, String)>>()
             .into_iter()
             .collect();
 
-        let mut tokens_data = vec![];
-
-        for (address, chain) in unique_tokens {
-            tokens_data.push(self.get_token_metadata((address, chain)))
-        }
-
-        let db_tokens: Vec<DatabaseErc20Token> = join_all(tokens_data)
-            .await
-            .into_iter()
-            .filter(|token| token.is_some())
-            .map(|token| token.unwrap())
-            .collect();
-
-        let mut query_builder =
-            QueryBuilder::new("UPSERT INTO erc20_tokens (address, chain, decimals, name, symbol) ");
+        let db_tokens = self.get_tokens_metadata(&unique_tokens).await;
 
         let tokens_amount = db_tokens.len();
 
-        let mut tokens_data = vec![];
-
-        for token in db_tokens {
-            let name = match token.name {
-                Some(name) => {
-                    let name_fixed: String = name.replace("'", "");
-
-                    let name_bytes = name_fixed.as_bytes();
-
-                    let name_parsed = String::from_utf8_lossy(name_bytes);
-
-                    format!("'{}'", name_parsed)
-                }
-                None => String::from("NULL"),
-            };
-
-            let symbol = match token.symbol {
-                Some(symbol) => {
-                    let symbol_fixed: String = symbol.replace("'", "");
-
-                    let symbol_bytes = symbol_fixed.as_bytes();
-
-                    let symbol_parsed = String::from_utf8_lossy(symbol_bytes);
-
-                    format!("'{}'", symbol_parsed)
-                }
-                None => String::from("NULL"),
-            };
-
-            tokens_data.push((
-                token.address,
-                token.chain,
-                token.decimals.unwrap(),
-                name,
-                symbol,
-            ));
-        }
-
-        query_builder.push_values(
-            &tokens_data,
-            |mut row, (address, chain, decimals, name, symbol)| {
-                row.push_bind(address)
-                    .push_bind(chain)
-                    .push_bind(decimals)
-                    .push_bind(name)
-                    .push_bind(symbol);
-            },
-        );
-
         if tokens_amount > 0 {
-            let query = query_builder.build();
-
-            query
-                .execute(connection)
-                .await
-                .expect("Unable to store erc20 balances into database");
+            self.store_tokens(db, &db_tokens).await?;
         }
 
         info!(
@@ -294,42 +295,284 @@ impl ERC20Tokens {
         Ok(())
     }
 
-    pub async fn get_token_metadata(
-        &self,
-        (address, chain): (String, String),
-    ) -> Option<DatabaseErc20Token> {
-        let chain_data = get_chain(chain.to_string());
+    /// Fetches again the metadata of the tokens that failed because of the RPC once their
+    /// next attempt is due.
+    pub async fn retry(&self, db: &Database) -> Result<()> {
+        let connection = db.get_connection();
 
-        let provider = match Provider::<Http>::try_from(chain_data.public_rpc) {
-            Ok(provider) => provider,
-            Err(_) => return None,
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT address, chain FROM erc20_tokens WHERE status = $1 AND next_attempt_at < now() LIMIT 500",
+        )
+        .bind(TOKEN_STATUS_RETRY_LATER)
+        .fetch_all(connection)
+        .await;
+
+        let tokens = match rows {
+            Ok(tokens) => tokens,
+            Err(_) => return Ok(()),
         };
 
-        let client = Arc::new(provider);
+        if tokens.is_empty() {
+            return Ok(());
+        }
 
-        let token = ERC20::new(address.parse::<Address>().unwrap(), Arc::clone(&client));
+        let db_tokens = self.get_tokens_metadata(&tokens).await;
 
-        let name: Option<String> = match token.name().call().await {
-            Ok(name) => Some(format!("{}", name.trim_matches(char::from(0)))),
-            Err(_) => None,
-        };
+        if !db_tokens.is_empty() {
+            self.store_tokens(db, &db_tokens).await?;
+        }
+
+        info!(
+            "ERC20Tokens: Retried {} erc20 tokens, {} fetched.",
+            db_tokens.len(),
+            db_tokens
+                .iter()
+                .filter(|token| token.status == TOKEN_STATUS_OK)
+                .count()
+        );
+
+        Ok(())
+    }
+
+    /// Stores the tokens metadata. Tokens to retry keep their previous metadata and are
+    /// scheduled with an exponential backoff based on the amount of attempts. The trust level
+    /// is only updated by tokens coming from a list.
+    pub async fn store_tokens(&self, db: &Database, tokens: &[DatabaseErc20Token]) -> Result<()> {
+        let connection = db.get_connection();
+
+        // Tokens lists can include the same token more than once.
+        let tokens: Vec<&DatabaseErc20Token> = tokens
+            .iter()
+            .map(|token| ((token.address.clone(), token.chain.clone()), token))
+            .collect::<HashMap<(String, String), &DatabaseErc20Token>>()
+            .into_values()
+            .collect();
+
+        let chunks = get_chunks(tokens.len(), DatabaseErc20Token::field_count() + 1);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "INSERT INTO erc20_tokens (address, chain, decimals, name, symbol, status, logo_uri, source, tags, trust, attempts, next_attempt_at) ",
+            );
+
+            query_builder.push_values(&tokens[start..end], |mut row, token| {
+                let retry = token.status == TOKEN_STATUS_RETRY_LATER;
+
+                row.push_bind(token.address.clone())
+                    .push_bind(token.chain.clone())
+                    .push_bind(token.decimals)
+                    .push_bind(token.name.as_ref().map(|name| format_text(name)))
+                    .push_bind(token.symbol.as_ref().map(|symbol| format_text(symbol)))
+                    .push_bind(token.status.clone())
+                    .push_bind(token.logo_uri.clone())
+                    .push_bind(token.source.clone())
+                    .push_bind(token.tags.clone())
+                    .push_bind(token.trust.clone())
+                    .push_bind(if retry { 1_i64 } else { 0 });
+
+                if retry {
+                    row.push("now() + INTERVAL '5 minutes'");
+                } else {
+                    row.push("NULL");
+                }
+            });
+
+            query_builder.push(" ON CONFLICT (address, chain) DO UPDATE SET decimals = COALESCE(excluded.decimals, erc20_tokens.decimals), name = COALESCE(excluded.name, erc20_tokens.name), symbol = COALESCE(excluded.symbol, erc20_tokens.symbol), status = excluded.status, logo_uri = COALESCE(excluded.logo_uri, erc20_tokens.logo_uri), source = COALESCE(excluded.source, erc20_tokens.source), tags = COALESCE(excluded.tags, erc20_tokens.tags), trust = CASE WHEN excluded.source IS NULL THEN erc20_tokens.trust ELSE excluded.trust END, attempts = CASE WHEN excluded.status = 'retry-later' THEN erc20_tokens.attempts + 1 ELSE 0 END, next_attempt_at = CASE WHEN excluded.status = 'retry-later' THEN now() + INTERVAL '5 minutes' * power(2, least(erc20_tokens.attempts, 10))::INT ELSE NULL END");
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store erc20 tokens into database");
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the metadata of the tokens grouped by chain, using a single `aggregate3` call
+    /// for each batch of tokens.
+    pub async fn get_tokens_metadata(
+        &self,
+        tokens: &[(String, String)],
+    ) -> Vec<DatabaseErc20Token> {
+        let mut tokens_by_chain: HashMap<String, Vec<String>> = HashMap::new();
+
+        for (address, chain) in tokens {
+            tokens_by_chain
+                .entry(chain.clone())
+                .or_default()
+                .push(address.clone());
+        }
+
+        let mut work = vec![];
+
+        for (chain, addresses) in tokens_by_chain.iter() {
+            for chunk in addresses.chunks(METADATA_BATCH_SIZE) {
+                work.push(self.get_tokens_metadata_batch(chain, chunk));
+            }
+        }
+
+        join_all(work).await.into_iter().flatten().collect()
+    }
 
-        let decimals: Option<i64> = match token.decimals().call().await {
-            Ok(decimals) => Some(decimals as i64),
-            Err(_) => Some(0),
+    /// When the `aggregate3` call reverts, e.g. a token consuming all the gas, the batch is split
+    /// in halves until the failing tokens are isolated and scheduled to retry later. When the
+    /// RPC fails, the remaining tokens are scheduled to retry later at once.
+    async fn get_tokens_metadata_batch(
+        &self,
+        chain: &str,
+        addresses: &[String],
+    ) -> Vec<DatabaseErc20Token> {
+        let retry_later = |addresses: &[String]| -> Vec<DatabaseErc20Token> {
+            addresses
+                .iter()
+                .map(|address| DatabaseErc20Token {
+                    address: address.clone(),
+                    chain: chain.to_owned(),
+                    name: None,
+                    decimals: None,
+                    symbol: None,
+                    status: TOKEN_STATUS_RETRY_LATER.to_owned(),
+                    logo_uri: None,
+                    source: None,
+                    tags: None,
+                    trust: TOKEN_TRUST_UNVERIFIED.to_owned(),
+                })
+                .collect()
         };
 
-        let symbol: Option<String> = match token.symbol().call().await {
-            Ok(symbol) => Some(format!("{}", symbol.trim_matches(char::from(0)))),
-            Err(_) => None,
+        let rpcs = get_chain(chain.to_owned()).get_rpcs();
+
+        let rpc = rpcs.choose(&mut rand::thread_rng()).unwrap();
+
+        let provider = match Provider::<Http>::try_from(rpc.as_str()) {
+            Ok(provider) => provider,
+            Err(_) => return retry_later(addresses),
         };
 
-        return Some(DatabaseErc20Token {
-            address,
-            chain,
-            name,
-            decimals,
-            symbol,
-        });
+        let mut tokens: Vec<DatabaseErc20Token> = Vec::new();
+
+        let mut pending: Vec<&[String]> = vec![addresses];
+
+        while let Some(batch) = pending.pop() {
+            match self
+                .get_tokens_metadata_calls(&provider, chain, batch)
+                .await
+            {
+                Ok(mut batch_tokens) => tokens.append(&mut batch_tokens),
+                Err(err) if is_execution_error(&err) && batch.len() > 1 => {
+                    let (left, right) = batch.split_at(batch.len() / 2);
+
+                    pending.push(left);
+                    pending.push(right);
+                }
+                Err(err) if is_execution_error(&err) => tokens.append(&mut retry_later(batch)),
+                Err(_) => {
+                    tokens.append(&mut retry_later(batch));
+
+                    for batch in pending.drain(..) {
+                        tokens.append(&mut retry_later(batch));
+                    }
+                }
+            }
+        }
+
+        tokens
     }
+
+    async fn get_tokens_metadata_calls(
+        &self,
+        provider: &Provider<Http>,
+        chain: &str,
+        addresses: &[String],
+    ) -> Result<Vec<DatabaseErc20Token>> {
+        let mut targets: Vec<&String> = Vec::new();
+
+        let mut calls: Vec<Call> = Vec::new();
+
+        for address in addresses {
+            let target = match address.parse::<Address>() {
+                Ok(target) => target,
+                Err(_) => continue,
+            };
+
+            targets.push(address);
+
+            for method in ["name()", "symbol()", "decimals()"] {
+                calls.push(Call {
+                    target,
+                    data: id(method).to_vec(),
+                });
+            }
+        }
+
+        let results = aggregate3(provider, &calls).await?;
+
+        let tokens = targets
+            .iter()
+            .zip(results.chunks(3))
+            .map(|(address, results)| {
+                let decimals = results[2].as_deref().and_then(decode_decimals);
+
+                let status = match decimals {
+                    Some(_) => TOKEN_STATUS_OK,
+                    None => TOKEN_STATUS_NOT_A_TOKEN,
+                };
+
+                DatabaseErc20Token {
+                    address: address.to_string(),
+                    chain: chain.to_owned(),
+                    name: results[0].as_deref().and_then(decode_text),
+                    decimals,
+                    symbol: results[1].as_deref().and_then(decode_text),
+                    status: status.to_owned(),
+                    logo_uri: None,
+                    source: None,
+                    tags: None,
+                    trust: TOKEN_TRUST_UNVERIFIED.to_owned(),
+                }
+            })
+            .collect();
+
+        Ok(tokens)
+    }
+}
+
+/// Decodes a `string` return value, or a `bytes32` for tokens like MKR.
+fn decode_text(data: &[u8]) -> Option<String> {
+    let text = match ethabi::decode(&[ParamType::String], data) {
+        Ok(tokens) => match tokens.into_iter().next() {
+            Some(Token::String(text)) => text,
+            _ => return None,
+        },
+        Err(_) if data.len() == 32 => String::from_utf8_lossy(data).to_string(),
+        Err(_) => return None,
+    };
+
+    let text = text.trim_matches(char::from(0)).to_owned();
+
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+fn decode_decimals(data: &[u8]) -> Option<i64> {
+    match ethabi::decode(&[ParamType::Uint(256)], data) {
+        Ok(tokens) => match tokens.into_iter().next() {
+            Some(Token::Uint(decimals)) if decimals <= 255.into() => Some(decimals.as_u64() as i64),
+            _ => None,
+        },
+        Err(_) => None,
+    }
+}
+
+fn format_text(text: &str) -> String {
+    let text_fixed: String = text.replace('\'', "");
+
+    let text_parsed = String::from_utf8_lossy(text_fixed.as_bytes());
+
+    format!("'{}'", text_parsed)
 }
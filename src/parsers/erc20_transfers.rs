@@ -1,10 +1,19 @@
-use crate::db::{
-    db::{get_chunks, Database},
-    models::models::DatabaseLog,
+use std::collections::HashSet;
+
+use crate::{
+    chains::chains::CHAINS,
+    db::{
+        db::{get_chunks, Database},
+        models::models::{
+            DatabaseAddressActivity, DatabaseLog, DatabaseLogWithBlock,
+            ACTIVITY_KIND_ERC20_TRANSFER_IN, ACTIVITY_KIND_ERC20_TRANSFER_OUT,
+        },
+    },
+    utils::format_address,
 };
 use anyhow::Result;
-use ethabi::{ethereum_types::H256, ParamType};
-use ethers::types::Bytes;
+use ethabi::{ethereum_types::H256, ParamType, Token};
+use ethers::types::{Bytes, H160};
 use field_count::FieldCount;
 use log::info;
 use sqlx::QueryBuilder;
@@ -20,14 +29,54 @@ pub struct DatabaseErc20Transfer {
     pub value: String,
 }
 
+impl DatabaseErc20Transfer {
+    /// Entries of the sender and the receiver, mints and burns only have the entry of the
+    /// holder.
+    pub fn to_activity(&self, block_number: i64) -> Vec<DatabaseAddressActivity> {
+        let zero_address = format_address(H160::zero());
+
+        let entry = |address: &String, counterparty: &String, kind: &str| DatabaseAddressActivity {
+            address: address.clone(),
+            amount: Some(self.value.clone()),
+            block_number,
+            chain: self.chain.clone(),
+            counterparty: Some(counterparty.clone()),
+            hash: self.hash.clone(),
+            kind: kind.to_owned(),
+            log_index: self.log_index,
+            token: Some(self.token.clone()),
+        };
+
+        let mut activity = Vec::new();
+
+        if self.from_address != zero_address {
+            activity.push(entry(
+                &self.from_address,
+                &self.to_address,
+                ACTIVITY_KIND_ERC20_TRANSFER_OUT,
+            ));
+        }
+
+        if self.to_address != zero_address {
+            activity.push(entry(
+                &self.to_address,
+                &self.from_address,
+                ACTIVITY_KIND_ERC20_TRANSFER_IN,
+            ));
+        }
+
+        activity
+    }
+}
+
 pub struct ERC20Transfers {}
 
 impl ERC20Transfers {
-    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseLog>> {
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseLogWithBlock>> {
         let connection = db.get_connection();
 
-        let rows = sqlx::query_as::<_, DatabaseLog>(
-            "SELECT * FROM logs WHERE erc20_transfers_parsed = NULL OR erc20_transfers_parsed = false LIMIT 500",
+        let rows = sqlx::query_as::<_, DatabaseLogWithBlock>(
+            "SELECT logs.*, transactions.block_number FROM logs INNER JOIN transactions ON logs.hash = transactions.hash WHERE logs.erc20_transfers_parsed = NULL OR logs.erc20_transfers_parsed = false LIMIT 500",
         )
         .fetch_all(connection)
         .await;
@@ -38,18 +87,43 @@ impl ERC20Transfers {
         }
     }
 
-    pub async fn parse(&self, db: &Database, logs: &Vec<DatabaseLog>) -> Result<()> {
+    pub async fn parse(&self, db: &Database, logs: &[DatabaseLogWithBlock]) -> Result<()> {
         let mut db_erc20_transfers = Vec::new();
 
+        let mut db_activity: Vec<DatabaseAddressActivity> = Vec::new();
+
         let mut db_parsed_logs = Vec::new();
 
-        for log in logs {
+        let wrapped_natives: HashSet<(String, String)> = CHAINS
+            .iter()
+            .filter_map(|chain| {
+                chain
+                    .wrapped_native
+                    .map(|address| (chain.name.to_owned(), address.to_owned()))
+            })
+            .collect();
+
+        for log_with_block in logs {
+            let log = &log_with_block.log;
+
             let mut parsed_log = log.to_owned();
 
             parsed_log.erc20_transfers_parsed = true;
 
             db_parsed_logs.push(parsed_log);
 
+            if log.topics.len() == 2
+                && wrapped_natives.contains(&(log.chain.clone(), log.address.clone()))
+            {
+                if let Some(db_transfer) = self.parse_wrapped_native(log) {
+                    db_activity.extend(db_transfer.to_activity(log_with_block.block_number));
+
+                    db_erc20_transfers.push(db_transfer);
+                }
+
+                continue;
+            }
+
             if log.topics.len() != 3 {
                 continue;
             }
@@ -137,6 +211,8 @@ impl ERC20Transfers {
                 value,
             };
 
+            db_activity.extend(db_transfers.to_activity(log_with_block.block_number));
+
             db_erc20_transfers.push(db_transfers)
         }
 
@@ -174,6 +250,10 @@ impl ERC20Transfers {
             }
         }
 
+        if !db_activity.is_empty() {
+            db.store_address_activity(&db_activity).await?;
+        }
+
         info!(
             "Inserted {} erc20 transfers to the database.",
             db_erc20_transfers.len()
@@ -207,4 +287,73 @@ impl ERC20Transfers {
 
         Ok(())
     }
+
+    /// Wrapped native tokens mint and burn through `Deposit(address,uint256)` and
+    /// `Withdrawal(address,uint256)` instead of `Transfer`, so these events are stored
+    /// as transfers from and to the zero address.
+    fn parse_wrapped_native(&self, log: &DatabaseLog) -> Option<DatabaseErc20Transfer> {
+        let inputs = |name: &str| {
+            vec![
+                ethabi::EventParam {
+                    name: name.to_owned(),
+                    kind: ParamType::Address,
+                    indexed: true,
+                },
+                ethabi::EventParam {
+                    name: "wad".to_owned(),
+                    kind: ParamType::Uint(256),
+                    indexed: false,
+                },
+            ]
+        };
+
+        let deposit = ethabi::Event {
+            name: "Deposit".to_owned(),
+            inputs: inputs("dst"),
+            anonymous: false,
+        };
+
+        let withdrawal = ethabi::Event {
+            name: "Withdrawal".to_owned(),
+            inputs: inputs("src"),
+            anonymous: false,
+        };
+
+        let topic_1 = log.topics[0].clone()?;
+
+        let is_deposit = topic_1 == format!("{:?}", deposit.signature());
+
+        if !is_deposit && topic_1 != format!("{:?}", withdrawal.signature()) {
+            return None;
+        }
+
+        let event = if is_deposit { deposit } else { withdrawal };
+
+        let decoded = event.parse_log(log.to_raw_log()?).ok()?;
+
+        let (account, value) = match (&decoded.params[0].value, &decoded.params[1].value) {
+            (Token::Address(account), Token::Uint(value)) => {
+                (format!("{:?}", account), format!("{:?}", value))
+            }
+            _ => return None,
+        };
+
+        let zero_address = format_address(H160::zero());
+
+        let (from_address, to_address) = if is_deposit {
+            (zero_address, account)
+        } else {
+            (account, zero_address)
+        };
+
+        Some(DatabaseErc20Transfer {
+            hash: log.hash.clone(),
+            chain: log.chain.to_owned(),
+            log_index: log.log_index,
+            token: log.address.clone(),
+            from_address,
+            to_address,
+            value,
+        })
+    }
 }
@@ -0,0 +1,239 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::db::db::{get_chunks, Database};
+use anyhow::Result;
+use ethabi::Event;
+use ethers::abi::parse_abi;
+use field_count::FieldCount;
+use log::info;
+use serde_json::Value;
+use sqlx::QueryBuilder;
+
+use super::contracts_code::{get_event_topics, get_selectors};
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DatabaseUnverifiedContract {
+    pub bytecode: String,
+    pub chain: String,
+    pub contract: String,
+}
+
+/// Contract with the ABI inferred from its bytecode.
+#[derive(Debug, Clone, FieldCount)]
+pub struct InferredAbi {
+    pub abi: String,
+    pub chain: String,
+    pub contract: String,
+}
+
+pub struct InferredAbis {}
+
+impl InferredAbis {
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseUnverifiedContract>> {
+        let connection = db.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseUnverifiedContract>(
+            "SELECT contracts_bytecode.bytecode, contracts.chain, contracts.contract FROM contracts INNER JOIN contracts_bytecode ON contracts.code_hash = contracts_bytecode.code_hash WHERE contracts.parsed = true AND contracts.verified = false AND contracts.abi_inferred = false LIMIT 500",
+        )
+        .fetch_all(connection)
+        .await;
+
+        match rows {
+            Ok(contracts) => Ok(contracts),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Builds a best-effort ABI for the unverified contracts with the selectors and event
+    /// topics found in their bytecode that match a known signature. Functions inputs come from
+    /// the signatures but outputs and state mutability are unknown.
+    pub async fn parse(
+        &self,
+        db: &Database,
+        contracts: &[DatabaseUnverifiedContract],
+    ) -> Result<()> {
+        let mut contracts_signatures: Vec<(&DatabaseUnverifiedContract, Vec<String>, Vec<String>)> =
+            Vec::new();
+
+        let mut all_selectors: HashSet<String> = HashSet::new();
+
+        let mut all_topics: HashSet<String> = HashSet::new();
+
+        for contract in contracts {
+            let code = match hex::decode(contract.bytecode.trim_start_matches("0x")) {
+                Ok(code) => code,
+                Err(_) => continue,
+            };
+
+            let selectors: Vec<String> = get_selectors(&code)
+                .into_iter()
+                .map(|selector| format!("0x{}", hex::encode(selector)))
+                .collect();
+
+            let topics: Vec<String> = get_event_topics(&code)
+                .into_iter()
+                .map(|topic| format!("0x{}", hex::encode(topic)))
+                .collect();
+
+            all_selectors.extend(selectors.iter().cloned());
+
+            all_topics.extend(topics.iter().cloned());
+
+            contracts_signatures.push((contract, selectors, topics));
+        }
+
+        let all_selectors: Vec<String> = all_selectors.into_iter().collect();
+
+        let all_topics: Vec<String> = all_topics.into_iter().collect();
+
+        let mut functions: HashMap<String, Value> = HashMap::new();
+
+        if !all_selectors.is_empty() {
+            for method in db.get_methods(&all_selectors).await? {
+                let signature = match method.signature {
+                    Some(signature) => signature,
+                    None => continue,
+                };
+
+                let mut function = match parse_abi(&[&format!("function {}", signature)]) {
+                    Ok(contract) => match contract.functions().next() {
+                        Some(function) => serde_json::to_value(function)?,
+                        None => continue,
+                    },
+                    Err(_) => continue,
+                };
+
+                function["type"] = Value::String("function".to_owned());
+
+                functions.insert(method.method, function);
+            }
+        }
+
+        // The same topic can be registered with different indexed arguments, all of them are
+        // kept as the right one is only known when decoding a log.
+        let mut events: HashMap<String, Vec<Value>> = HashMap::new();
+
+        if !all_topics.is_empty() {
+            for db_event in db.get_events(&all_topics).await? {
+                if let Ok(event) = serde_json::from_str::<Event>(&db_event.abi) {
+                    let mut value = serde_json::to_value(&event)?;
+
+                    value["type"] = Value::String("event".to_owned());
+
+                    events.entry(db_event.topic).or_default().push(value);
+                }
+            }
+        }
+
+        let mut inferred_abis: Vec<InferredAbi> = Vec::new();
+
+        for (contract, selectors, topics) in contracts_signatures {
+            let mut abi: Vec<Value> = selectors
+                .iter()
+                .filter_map(|selector| functions.get(selector).cloned())
+                .collect();
+
+            for topic in topics {
+                if let Some(topic_events) = events.get(&topic) {
+                    abi.extend(topic_events.iter().cloned());
+                }
+            }
+
+            if abi.is_empty() {
+                continue;
+            }
+
+            inferred_abis.push(InferredAbi {
+                abi: Value::Array(abi).to_string(),
+                chain: contract.chain.clone(),
+                contract: contract.contract.clone(),
+            });
+        }
+
+        if !inferred_abis.is_empty() {
+            self.store_inferred_abis(db, &inferred_abis).await?;
+        }
+
+        let parsed: Vec<(String, String)> = contracts
+            .iter()
+            .map(|contract| (contract.contract.clone(), contract.chain.clone()))
+            .collect();
+
+        if !parsed.is_empty() {
+            self.update_contracts_inferred(db, &parsed).await?;
+        }
+
+        info!(
+            "InferredAbis: Inferred {} ABIs from {} unverified contracts.",
+            inferred_abis.len(),
+            contracts.len()
+        );
+
+        Ok(())
+    }
+
+    /// Only contracts still without an ABI are updated, so a verified ABI stored meanwhile is
+    /// never replaced.
+    async fn store_inferred_abis(&self, db: &Database, abis: &[InferredAbi]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(abis.len(), InferredAbi::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPDATE contracts_information SET abi = data.abi, inferred = true FROM (",
+            );
+
+            query_builder.push_values(&abis[start..end], |mut row, inferred_abi| {
+                row.push_bind(inferred_abi.abi.clone())
+                    .push_unseparated("::TEXT")
+                    .push_bind(inferred_abi.chain.clone())
+                    .push_unseparated("::TEXT")
+                    .push_bind(inferred_abi.contract.clone())
+                    .push_unseparated("::TEXT");
+            });
+
+            query_builder.push(
+                ") AS data (abi, chain, contract) WHERE contracts_information.contract = data.contract AND contracts_information.chain = data.chain AND contracts_information.abi IS NULL",
+            );
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store inferred abis into database");
+        }
+
+        Ok(())
+    }
+
+    async fn update_contracts_inferred(
+        &self,
+        db: &Database,
+        contracts: &[(String, String)],
+    ) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(contracts.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPDATE contracts SET abi_inferred = true WHERE (contract, chain) IN ",
+            );
+
+            query_builder.push_tuples(&contracts[start..end], |mut row, (contract, chain)| {
+                row.push_bind(contract.clone()).push_bind(chain.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to update contracts into database");
+        }
+
+        Ok(())
+    }
+}
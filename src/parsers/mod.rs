@@ -1,3 +1,16 @@
+pub mod contracts_code;
+pub mod decoded_logs;
+pub mod decoded_transactions;
+pub mod dex_trades;
+pub mod ens_names;
+pub mod erc20_approvals;
 pub mod erc20_balances;
+pub mod erc20_holders;
+pub mod erc20_supply;
 pub mod erc20_tokens;
 pub mod erc20_transfers;
+pub mod inferred_abis;
+pub mod proxy_upgrades;
+pub mod safes;
+pub mod token_flags;
+pub mod token_prices;
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+
+use crate::{
+    db::{
+        db::{get_chunks, Database},
+        models::models::{DatabaseContractImplementation, DatabaseLog, DatabaseLogWithBlock},
+    },
+    rpc::proxies::PROXY_STANDARD_UPGRADED_EVENT,
+};
+use anyhow::Result;
+use ethabi::{ParamType, Token};
+use ethers::utils::keccak256;
+use log::info;
+use sqlx::QueryBuilder;
+
+pub struct ProxyUpgrades {}
+
+impl ProxyUpgrades {
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseLogWithBlock>> {
+        let connection = db.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseLogWithBlock>(
+            "SELECT logs.*, transactions.block_number FROM logs INNER JOIN transactions ON logs.hash = transactions.hash WHERE logs.proxy_upgrades_parsed = false LIMIT 500",
+        )
+        .fetch_all(connection)
+        .await;
+
+        match rows {
+            Ok(logs) => Ok(logs),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Stores the implementations set by the `Upgraded` events of EIP-1967 proxies and
+    /// queues the proxies to fetch again their ABI merged with the new implementation.
+    pub async fn parse(&self, db: &Database, logs: &[DatabaseLogWithBlock]) -> Result<()> {
+        let signature = format!("0x{}", hex::encode(keccak256("Upgraded(address)")));
+
+        let mut db_implementations: Vec<DatabaseContractImplementation> = Vec::new();
+
+        let mut db_parsed_logs: Vec<DatabaseLog> = Vec::new();
+
+        for log_with_block in logs {
+            let log = &log_with_block.log;
+
+            db_parsed_logs.push(log.to_owned());
+
+            if log.topics.len() != 2 || log.topics[0] != Some(signature.clone()) {
+                continue;
+            }
+
+            let topic = match &log.topics[1] {
+                Some(topic) => topic,
+                None => continue,
+            };
+
+            let implementation = match hex::decode(topic.trim_start_matches("0x")) {
+                Ok(data) => match ethabi::decode(&[ParamType::Address], &data) {
+                    Ok(tokens) => match tokens.first() {
+                        Some(Token::Address(implementation)) => format!("{:?}", implementation),
+                        _ => continue,
+                    },
+                    Err(_) => continue,
+                },
+                Err(_) => continue,
+            };
+
+            db_implementations.push(DatabaseContractImplementation {
+                block: log_with_block.block_number,
+                chain: log.chain.clone(),
+                contract: log.address.clone(),
+                implementation,
+                standard: PROXY_STANDARD_UPGRADED_EVENT.to_owned(),
+            });
+        }
+
+        if !db_implementations.is_empty() {
+            db.store_contracts_implementations(&db_implementations)
+                .await
+                .unwrap();
+
+            let proxies: Vec<(String, String)> = db_implementations
+                .iter()
+                .map(|implementation| {
+                    (
+                        implementation.contract.clone(),
+                        implementation.chain.clone(),
+                    )
+                })
+                .collect::<HashSet<(String, String)>>()
+                .into_iter()
+                .collect();
+
+            self.refresh_contracts(db, &proxies).await?;
+        }
+
+        info!(
+            "ProxyUpgrades: Inserted {} proxy upgrades to the database.",
+            db_implementations.len()
+        );
+
+        if !db_parsed_logs.is_empty() {
+            db.update_logs_parsed("proxy_upgrades_parsed", &db_parsed_logs)
+                .await
+                .unwrap();
+        }
+
+        Ok(())
+    }
+
+    /// Marks the contracts as not parsed so the ABI fetcher resolves their implementation
+    /// again.
+    async fn refresh_contracts(&self, db: &Database, contracts: &[(String, String)]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(contracts.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPDATE contracts SET parsed = false, next_attempt_at = NULL WHERE (contract, chain) IN ",
+            );
+
+            query_builder.push_tuples(&contracts[start..end], |mut row, (contract, chain)| {
+                row.push_bind(contract.clone()).push_bind(chain.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to update contracts into database");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,785 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    db::{
+        db::{get_chunks, Database},
+        models::models::{DatabaseLog, DatabaseLogWithBlock},
+    },
+    utils::{format_address, format_hash},
+};
+use anyhow::Result;
+use ethabi::{ParamType, Token};
+use ethers::{types::H256, utils::keccak256};
+use field_count::FieldCount;
+use log::info;
+use sqlx::QueryBuilder;
+
+/// Selector of `execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)`.
+pub const EXEC_TRANSACTION_SELECTOR: &str = "0x6a761202";
+
+/// Safe proxy factories v1.0.0, v1.1.1, v1.3.0 (and its EIP-155 deployment) and v1.4.1,
+/// deployed at the same address on every chain.
+pub const SAFE_PROXY_FACTORIES: &[&str] = &[
+    "0x12302fe9c02ff50939baaaaf415fc226c078613c",
+    "0x76e2cfc1f5fa8f6a5b3fc4c8f4788f0116861f9b",
+    "0xa6b71e26c5e0845f74c812102ca7114b6a896ab2",
+    "0xc22834581ebc8527d974f8a1c97e1bea4ef910bc",
+    "0x4e1dcf7ad4e460cfd30791ccc4f9c8a4f820ec67",
+];
+
+/// Safe proxy detected from its creation by one of the known proxy factories or from its
+/// setup.
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseSafe {
+    pub address: String,
+    pub chain: String,
+    pub created_block: Option<i64>,
+    pub factory: Option<String>,
+    pub singleton: Option<String>,
+}
+
+/// Owner added to or removed from a Safe, the current owners are the ones whose last
+/// change is an addition.
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseSafeOwner {
+    pub added: bool,
+    pub block_number: i64,
+    pub chain: String,
+    pub hash: String,
+    pub log_index: i64,
+    pub owner: String,
+    pub safe: String,
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseSafeThreshold {
+    pub block_number: i64,
+    pub chain: String,
+    pub hash: String,
+    pub log_index: i64,
+    pub safe: String,
+    pub threshold: i64,
+}
+
+/// Executed Safe transaction, the inner call is only known when the transaction called
+/// `execTransaction` on the Safe directly.
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseSafeExecution {
+    pub block_number: i64,
+    pub chain: String,
+    pub data: Option<String>,
+    pub hash: String,
+    pub log_index: i64,
+    pub method: Option<String>,
+    pub operation: Option<i64>,
+    pub payment: String,
+    pub safe: String,
+    pub safe_tx_hash: String,
+    pub success: bool,
+    pub to_address: Option<String>,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct DatabaseTransactionCall {
+    hash: String,
+    input: String,
+    to_address: String,
+}
+
+/// Inner call of an `execTransaction`.
+struct SafeCall {
+    data: String,
+    method: String,
+    operation: i64,
+    to_address: String,
+    value: String,
+}
+
+pub struct Safes {}
+
+impl Safes {
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseLogWithBlock>> {
+        let connection = db.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseLogWithBlock>(
+            "SELECT logs.*, transactions.block_number FROM logs INNER JOIN transactions ON logs.hash = transactions.hash WHERE logs.safes_parsed = false ORDER BY transactions.block_number, logs.log_index LIMIT 500",
+        )
+        .fetch_all(connection)
+        .await;
+
+        match rows {
+            Ok(logs) => Ok(logs),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Stores the Safes created by the proxy factories, the history of their owners and
+    /// thresholds, and their executed transactions. Events of Safe v1.1 to v1.4 are parsed,
+    /// newer versions index some of the arguments. Owners, thresholds and executions are
+    /// only taken from the Safes already detected, as their events are common to other
+    /// contracts. The logs of a contract whose setup is not parsed yet are left for the next
+    /// batches.
+    pub async fn parse(&self, db: &Database, logs: &[DatabaseLogWithBlock]) -> Result<()> {
+        let signature = |event: &str| format!("0x{}", hex::encode(keccak256(event)));
+
+        let proxy_creation = signature("ProxyCreation(address,address)");
+        let proxy_creation_legacy = signature("ProxyCreation(address)");
+        let safe_setup = signature("SafeSetup(address,address[],uint256,address,address)");
+        let added_owner = signature("AddedOwner(address)");
+        let removed_owner = signature("RemovedOwner(address)");
+        let changed_threshold = signature("ChangedThreshold(uint256)");
+        let execution_success = signature("ExecutionSuccess(bytes32,uint256)");
+        let execution_failure = signature("ExecutionFailure(bytes32,uint256)");
+
+        let mut db_safes: HashMap<(String, String), DatabaseSafe> = HashMap::new();
+
+        let mut db_owners: Vec<DatabaseSafeOwner> = Vec::new();
+
+        let mut db_thresholds: Vec<DatabaseSafeThreshold> = Vec::new();
+
+        let mut db_executions: Vec<DatabaseSafeExecution> = Vec::new();
+
+        let mut db_parsed_logs: Vec<DatabaseLog> = Vec::new();
+
+        // Safes are detected first as their setup is emitted before their creation.
+        for log_with_block in logs {
+            let log = &log_with_block.log;
+
+            if log.removed {
+                continue;
+            }
+
+            let topics: Vec<&String> = log.topics.iter().flatten().collect();
+
+            let topic = match topics.first() {
+                Some(topic) => topic.to_string(),
+                None => continue,
+            };
+
+            if topic == safe_setup && topics.len() == 2 {
+                db_safes
+                    .entry((log.address.clone(), log.chain.clone()))
+                    .or_insert_with(|| DatabaseSafe {
+                        address: log.address.clone(),
+                        chain: log.chain.clone(),
+                        created_block: None,
+                        factory: None,
+                        singleton: None,
+                    });
+
+                continue;
+            }
+
+            if (topic != proxy_creation && topic != proxy_creation_legacy)
+                || !SAFE_PROXY_FACTORIES.contains(&log.address.as_str())
+            {
+                continue;
+            }
+
+            let data = match hex::decode(log.data.trim_start_matches("0x")) {
+                Ok(data) => data,
+                Err(_) => continue,
+            };
+
+            let (proxy, singleton) = match (topic == proxy_creation, topics.len()) {
+                (true, 1) => match decode(&[ParamType::Address, ParamType::Address], &data) {
+                    Some(tokens) => (to_address(&tokens[0]), to_address(&tokens[1])),
+                    None => continue,
+                },
+                (true, 2) => match decode(&[ParamType::Address], &data) {
+                    Some(tokens) => (topic_to_address(topics[1]), to_address(&tokens[0])),
+                    None => continue,
+                },
+                (false, 1) => match decode(&[ParamType::Address], &data) {
+                    Some(tokens) => (to_address(&tokens[0]), None),
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            let proxy = match proxy {
+                Some(proxy) => proxy,
+                None => continue,
+            };
+
+            db_safes.insert(
+                (proxy.clone(), log.chain.clone()),
+                DatabaseSafe {
+                    address: proxy,
+                    chain: log.chain.clone(),
+                    created_block: Some(log_with_block.block_number),
+                    factory: Some(log.address.clone()),
+                    singleton,
+                },
+            );
+        }
+
+        let emitters: Vec<(String, String)> = logs
+            .iter()
+            .map(|log| (log.log.address.clone(), log.log.chain.clone()))
+            .filter(|emitter| !db_safes.contains_key(emitter))
+            .collect::<HashSet<(String, String)>>()
+            .into_iter()
+            .collect();
+
+        let mut safes: HashSet<(String, String)> = self.get_safes(db, &emitters).await?;
+
+        safes.extend(db_safes.keys().cloned());
+
+        let unknown: Vec<(String, String)> = emitters
+            .into_iter()
+            .filter(|emitter| !safes.contains(emitter))
+            .collect();
+
+        let pending = self.get_pending_setups(db, &unknown, &safe_setup).await?;
+
+        for log_with_block in logs {
+            let log = &log_with_block.log;
+
+            if pending.contains(&(log.address.clone(), log.chain.clone())) {
+                continue;
+            }
+
+            db_parsed_logs.push(log.to_owned());
+
+            if log.removed {
+                continue;
+            }
+
+            let topics: Vec<&String> = log.topics.iter().flatten().collect();
+
+            let topic = match topics.first() {
+                Some(topic) => topic.to_string(),
+                None => continue,
+            };
+
+            let data = match hex::decode(log.data.trim_start_matches("0x")) {
+                Ok(data) => data,
+                Err(_) => continue,
+            };
+
+            let block_number = log_with_block.block_number;
+
+            let owner = |owner: String, added: bool| DatabaseSafeOwner {
+                added,
+                block_number,
+                chain: log.chain.clone(),
+                hash: log.hash.clone(),
+                log_index: log.log_index,
+                owner,
+                safe: log.address.clone(),
+            };
+
+            let threshold = |threshold: i64| DatabaseSafeThreshold {
+                block_number,
+                chain: log.chain.clone(),
+                hash: log.hash.clone(),
+                log_index: log.log_index,
+                safe: log.address.clone(),
+                threshold,
+            };
+
+            if !safes.contains(&(log.address.clone(), log.chain.clone())) {
+                continue;
+            }
+
+            if topic == safe_setup && topics.len() == 2 {
+                let tokens = match decode(
+                    &[
+                        ParamType::Array(Box::new(ParamType::Address)),
+                        ParamType::Uint(256),
+                        ParamType::Address,
+                        ParamType::Address,
+                    ],
+                    &data,
+                ) {
+                    Some(tokens) => tokens,
+                    None => continue,
+                };
+
+                if let Token::Array(owners) = &tokens[0] {
+                    for setup_owner in owners.iter().filter_map(to_address) {
+                        db_owners.push(owner(setup_owner, true));
+                    }
+                }
+
+                if let Some(value) = to_small_number(&tokens[1]) {
+                    db_thresholds.push(threshold(value));
+                }
+            } else if topic == added_owner || topic == removed_owner {
+                let changed_owner = match topics.len() {
+                    1 => decode(&[ParamType::Address], &data)
+                        .and_then(|tokens| to_address(&tokens[0])),
+                    2 => topic_to_address(topics[1]),
+                    _ => continue,
+                };
+
+                match changed_owner {
+                    Some(changed_owner) => {
+                        db_owners.push(owner(changed_owner, topic == added_owner))
+                    }
+                    None => continue,
+                }
+            } else if topic == changed_threshold && topics.len() == 1 {
+                match decode(&[ParamType::Uint(256)], &data)
+                    .and_then(|tokens| to_small_number(&tokens[0]))
+                {
+                    Some(value) => db_thresholds.push(threshold(value)),
+                    None => continue,
+                }
+            } else if topic == execution_success || topic == execution_failure {
+                let (safe_tx_hash, payment) = match topics.len() {
+                    1 => match decode(&[ParamType::FixedBytes(32), ParamType::Uint(256)], &data) {
+                        Some(tokens) => match (&tokens[0], &tokens[1]) {
+                            (Token::FixedBytes(hash), Token::Uint(payment)) => {
+                                (format_hash(H256::from_slice(hash)), payment.to_string())
+                            }
+                            _ => continue,
+                        },
+                        None => continue,
+                    },
+                    2 => match decode(&[ParamType::Uint(256)], &data) {
+                        Some(tokens) => match &tokens[0] {
+                            Token::Uint(payment) => (topics[1].to_lowercase(), payment.to_string()),
+                            _ => continue,
+                        },
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+
+                db_executions.push(DatabaseSafeExecution {
+                    block_number,
+                    chain: log.chain.clone(),
+                    data: None,
+                    hash: log.hash.clone(),
+                    log_index: log.log_index,
+                    method: None,
+                    operation: None,
+                    payment,
+                    safe: log.address.clone(),
+                    safe_tx_hash,
+                    success: topic == execution_success,
+                    to_address: None,
+                    value: None,
+                });
+            }
+        }
+
+        self.add_executions_calls(db, &mut db_executions).await?;
+
+        let db_safes: Vec<DatabaseSafe> = db_safes.into_values().collect();
+
+        if !db_safes.is_empty() {
+            self.store_safes(db, &db_safes).await?;
+        }
+
+        if !db_owners.is_empty() {
+            self.store_owners(db, &db_owners).await?;
+        }
+
+        if !db_thresholds.is_empty() {
+            self.store_thresholds(db, &db_thresholds).await?;
+        }
+
+        if !db_executions.is_empty() {
+            self.store_executions(db, &db_executions).await?;
+        }
+
+        info!(
+            "Safes: Inserted {} safes {} owners changes {} thresholds changes and {} executions to the database.",
+            db_safes.len(),
+            db_owners.len(),
+            db_thresholds.len(),
+            db_executions.len()
+        );
+
+        if !db_parsed_logs.is_empty() {
+            db.update_logs_parsed("safes_parsed", &db_parsed_logs)
+                .await
+                .unwrap();
+        }
+
+        Ok(())
+    }
+
+    /// Returns the contracts already stored as Safes.
+    async fn get_safes(
+        &self,
+        db: &Database,
+        contracts: &[(String, String)],
+    ) -> Result<HashSet<(String, String)>> {
+        let mut safes: HashSet<(String, String)> = HashSet::new();
+
+        if contracts.is_empty() {
+            return Ok(safes);
+        }
+
+        let connection = db.get_connection();
+
+        for (start, end) in get_chunks(contracts.len(), 2) {
+            let mut query_builder =
+                QueryBuilder::new("SELECT address, chain FROM safes WHERE (address, chain) IN ");
+
+            query_builder.push_tuples(&contracts[start..end], |mut row, (address, chain)| {
+                row.push_bind(address.clone()).push_bind(chain.clone());
+            });
+
+            let rows = query_builder
+                .build_query_as::<(String, String)>()
+                .fetch_all(connection)
+                .await?;
+
+            safes.extend(rows);
+        }
+
+        Ok(safes)
+    }
+
+    /// Returns the contracts with a `SafeSetup` log not parsed yet.
+    async fn get_pending_setups(
+        &self,
+        db: &Database,
+        contracts: &[(String, String)],
+        safe_setup: &str,
+    ) -> Result<HashSet<(String, String)>> {
+        let mut pending: HashSet<(String, String)> = HashSet::new();
+
+        if contracts.is_empty() {
+            return Ok(pending);
+        }
+
+        let connection = db.get_connection();
+
+        for (start, end) in get_chunks(contracts.len(), 2) {
+            let mut query_builder = QueryBuilder::new(
+                "SELECT DISTINCT address, chain FROM logs WHERE safes_parsed = false AND topics[1] = ",
+            );
+
+            query_builder
+                .push_bind(safe_setup.to_owned())
+                .push(" AND (address, chain) IN ");
+
+            query_builder.push_tuples(&contracts[start..end], |mut row, (address, chain)| {
+                row.push_bind(address.clone()).push_bind(chain.clone());
+            });
+
+            let rows = query_builder
+                .build_query_as::<(String, String)>()
+                .fetch_all(connection)
+                .await?;
+
+            pending.extend(rows);
+        }
+
+        Ok(pending)
+    }
+
+    /// Returns the current owners of a Safe.
+    pub async fn get_owners(&self, db: &Database, safe: &str, chain: &str) -> Result<Vec<String>> {
+        let connection = db.get_connection();
+
+        let owners = sqlx::query_scalar::<_, String>(
+            "SELECT owner FROM (SELECT DISTINCT ON (owner) owner, added FROM safes_owners WHERE safe = $1 AND chain = $2 ORDER BY owner, block_number DESC, log_index DESC) WHERE added = true",
+        )
+        .bind(safe.to_lowercase())
+        .bind(chain.to_owned())
+        .fetch_all(connection)
+        .await?;
+
+        Ok(owners)
+    }
+
+    /// Returns the current amount of owners required to execute a transaction of a Safe.
+    pub async fn get_threshold(
+        &self,
+        db: &Database,
+        safe: &str,
+        chain: &str,
+    ) -> Result<Option<i64>> {
+        let connection = db.get_connection();
+
+        let threshold = sqlx::query_scalar::<_, i64>(
+            "SELECT threshold FROM safes_thresholds WHERE safe = $1 AND chain = $2 ORDER BY block_number DESC, log_index DESC LIMIT 1",
+        )
+        .bind(safe.to_lowercase())
+        .bind(chain.to_owned())
+        .fetch_optional(connection)
+        .await?;
+
+        Ok(threshold)
+    }
+
+    /// Decodes the inner call of the executions whose transaction called `execTransaction` on
+    /// the Safe itself. Calls through relayers or modules are left without it.
+    async fn add_executions_calls(
+        &self,
+        db: &Database,
+        executions: &mut [DatabaseSafeExecution],
+    ) -> Result<()> {
+        let mut hashes: Vec<String> = executions
+            .iter()
+            .map(|execution| execution.hash.clone())
+            .collect();
+
+        hashes.sort();
+        hashes.dedup();
+
+        if hashes.is_empty() {
+            return Ok(());
+        }
+
+        let connection = db.get_connection();
+
+        let mut calls: HashMap<String, DatabaseTransactionCall> = HashMap::new();
+
+        for (start, end) in get_chunks(hashes.len(), 1) {
+            let mut query_builder = QueryBuilder::new(
+                "SELECT hash, input, to_address FROM transactions WHERE method = ",
+            );
+
+            query_builder
+                .push_bind(EXEC_TRANSACTION_SELECTOR)
+                .push(" AND hash IN ");
+
+            query_builder.push_tuples(&hashes[start..end], |mut row, hash| {
+                row.push_bind(hash.clone());
+            });
+
+            let rows = query_builder
+                .build_query_as::<DatabaseTransactionCall>()
+                .fetch_all(connection)
+                .await?;
+
+            for row in rows {
+                calls.insert(row.hash.clone(), row);
+            }
+        }
+
+        for execution in executions.iter_mut() {
+            let call = match calls.get(&execution.hash) {
+                Some(call) if call.to_address == execution.safe => call,
+                _ => continue,
+            };
+
+            if let Some(safe_call) = decode_exec_transaction(&call.input) {
+                execution.data = Some(safe_call.data);
+                execution.method = Some(safe_call.method);
+                execution.operation = Some(safe_call.operation);
+                execution.to_address = Some(safe_call.to_address);
+                execution.value = Some(safe_call.value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the creation details of a Safe when it is stored again from its other events.
+    async fn store_safes(&self, db: &Database, safes: &[DatabaseSafe]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(safes.len(), DatabaseSafe::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "INSERT INTO safes (address, chain, created_block, factory, singleton) ",
+            );
+
+            query_builder.push_values(&safes[start..end], |mut row, safe| {
+                row.push_bind(safe.address.clone())
+                    .push_bind(safe.chain.clone())
+                    .push_bind(safe.created_block)
+                    .push_bind(safe.factory.clone())
+                    .push_bind(safe.singleton.clone());
+            });
+
+            query_builder.push(" ON CONFLICT (address, chain) DO UPDATE SET created_block = COALESCE(excluded.created_block, safes.created_block), factory = COALESCE(excluded.factory, safes.factory), singleton = COALESCE(excluded.singleton, safes.singleton)");
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store safes into database");
+        }
+
+        Ok(())
+    }
+
+    async fn store_owners(&self, db: &Database, owners: &[DatabaseSafeOwner]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(owners.len(), DatabaseSafeOwner::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO safes_owners (added, block_number, chain, hash, log_index, owner, safe) ",
+            );
+
+            query_builder.push_values(&owners[start..end], |mut row, owner| {
+                row.push_bind(owner.added)
+                    .push_bind(owner.block_number)
+                    .push_bind(owner.chain.clone())
+                    .push_bind(owner.hash.clone())
+                    .push_bind(owner.log_index)
+                    .push_bind(owner.owner.clone())
+                    .push_bind(owner.safe.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store safes owners into database");
+        }
+
+        Ok(())
+    }
+
+    async fn store_thresholds(
+        &self,
+        db: &Database,
+        thresholds: &[DatabaseSafeThreshold],
+    ) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(thresholds.len(), DatabaseSafeThreshold::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO safes_thresholds (block_number, chain, hash, log_index, safe, threshold) ",
+            );
+
+            query_builder.push_values(&thresholds[start..end], |mut row, threshold| {
+                row.push_bind(threshold.block_number)
+                    .push_bind(threshold.chain.clone())
+                    .push_bind(threshold.hash.clone())
+                    .push_bind(threshold.log_index)
+                    .push_bind(threshold.safe.clone())
+                    .push_bind(threshold.threshold);
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store safes thresholds into database");
+        }
+
+        Ok(())
+    }
+
+    async fn store_executions(
+        &self,
+        db: &Database,
+        executions: &[DatabaseSafeExecution],
+    ) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(executions.len(), DatabaseSafeExecution::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO safes_executions (block_number, chain, data, hash, log_index, method, operation, payment, safe, safe_tx_hash, success, to_address, value) ",
+            );
+
+            query_builder.push_values(&executions[start..end], |mut row, execution| {
+                row.push_bind(execution.block_number)
+                    .push_bind(execution.chain.clone())
+                    .push_bind(execution.data.clone())
+                    .push_bind(execution.hash.clone())
+                    .push_bind(execution.log_index)
+                    .push_bind(execution.method.clone())
+                    .push_bind(execution.operation)
+                    .push_bind(execution.payment.clone())
+                    .push_bind(execution.safe.clone())
+                    .push_bind(execution.safe_tx_hash.clone())
+                    .push_bind(execution.success)
+                    .push_bind(execution.to_address.clone())
+                    .push_bind(execution.value.clone());
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store safes executions into database");
+        }
+
+        Ok(())
+    }
+}
+
+fn decode_exec_transaction(input: &str) -> Option<SafeCall> {
+    let input = hex::decode(input.trim_start_matches("0x")).ok()?;
+
+    if input.len() < 4 {
+        return None;
+    }
+
+    let tokens = decode(
+        &[
+            ParamType::Address,
+            ParamType::Uint(256),
+            ParamType::Bytes,
+            ParamType::Uint(8),
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            ParamType::Address,
+            ParamType::Address,
+            ParamType::Bytes,
+        ],
+        &input[4..],
+    )?;
+
+    let data = match &tokens[2] {
+        Token::Bytes(data) => data.clone(),
+        _ => return None,
+    };
+
+    let method = if data.len() >= 4 {
+        format!("0x{}", hex::encode(&data[..4]))
+    } else {
+        "0x".to_owned()
+    };
+
+    Some(SafeCall {
+        data: format!("0x{}", hex::encode(&data)),
+        method,
+        operation: to_small_number(&tokens[3])?,
+        to_address: to_address(&tokens[0])?,
+        value: match &tokens[1] {
+            Token::Uint(value) => value.to_string(),
+            _ => return None,
+        },
+    })
+}
+
+fn decode(params: &[ParamType], data: &[u8]) -> Option<Vec<Token>> {
+    ethabi::decode(params, data).ok()
+}
+
+fn to_address(token: &Token) -> Option<String> {
+    match token {
+        Token::Address(address) => Some(format_address(*address)),
+        _ => None,
+    }
+}
+
+fn to_small_number(token: &Token) -> Option<i64> {
+    match token {
+        Token::Uint(value) if *value <= i64::MAX.into() => Some(value.as_u64() as i64),
+        _ => None,
+    }
+}
+
+fn topic_to_address(topic: &str) -> Option<String> {
+    let data = hex::decode(topic.trim_start_matches("0x")).ok()?;
+
+    to_address(decode(&[ParamType::Address], &data)?.first()?)
+}
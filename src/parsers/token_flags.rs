@@ -0,0 +1,334 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    db::db::{get_chunks, Database},
+    utils::format_address,
+};
+use anyhow::Result;
+use ethers::types::H160;
+use field_count::FieldCount;
+use log::info;
+use sqlx::QueryBuilder;
+
+use super::erc20_tokens::{
+    DatabaseErc20Token, TOKEN_STATUS_OK, TOKEN_TRUST_CURATED, TOKEN_TRUST_UNVERIFIED,
+};
+
+/// Tokens with a score equal or higher are flagged as spam.
+pub const SPAM_SCORE_THRESHOLD: i64 = 50;
+
+/// Amount of fresh receivers of a single sender in one transaction considered an airdrop.
+pub const AIRDROP_RECEIVERS: i64 = 1000;
+
+/// Blocks scanned for airdrops back from the latest indexed block of each chain.
+pub const AIRDROP_BLOCKS: i64 = 50_000;
+
+/// Amount of senders that never received the token needed to consider its transfers fake. A
+/// token minting its initial supply without a `Transfer` event has a single one.
+pub const FAKE_TRANSFERS_SENDERS: i64 = 5;
+
+/// Tokens are classified again after this amount of hours.
+pub const RECLASSIFY_HOURS: i64 = 24;
+
+/// Spam tokens usually advertise a website to claim the airdrop.
+pub const URL_PATTERNS: &[&str] = &[
+    "http", "www.", ".com", ".io", ".org", ".net", ".xyz", ".finance", "t.me/",
+];
+
+pub const REASON_AIRDROP: (&str, i64) = ("airdrop", 40);
+pub const REASON_URL_NAME: (&str, i64) = ("url-in-name", 60);
+pub const REASON_NOT_LISTED: (&str, i64) = ("not-listed", 20);
+pub const REASON_UNVERIFIED_CONTRACT: (&str, i64) = ("unverified-contract", 20);
+pub const REASON_FAKE_TRANSFERS: (&str, i64) = ("fake-transfers", 30);
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseTokenFlag {
+    pub chain: String,
+    pub reasons: Vec<String>,
+    pub score: i64,
+    pub spam: bool,
+    pub token: String,
+}
+
+pub struct TokenFlags {}
+
+impl TokenFlags {
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseErc20Token>> {
+        let connection = db.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseErc20Token>(
+            "SELECT erc20_tokens.* FROM erc20_tokens LEFT JOIN token_flags ON erc20_tokens.address = token_flags.token AND erc20_tokens.chain = token_flags.chain WHERE erc20_tokens.status = $1 AND (token_flags.token IS NULL OR token_flags.updated_at < now() - INTERVAL '1 hour' * $2) LIMIT 200",
+        )
+        .bind(TOKEN_STATUS_OK)
+        .bind(RECLASSIFY_HOURS)
+        .fetch_all(connection)
+        .await;
+
+        match rows {
+            Ok(tokens) => Ok(tokens),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Scores each token with the signals found in the indexed data. Tokens included in a
+    /// curated list are never flagged as spam, but keep their score and reasons, the transfers
+    /// based signals are not computed for them.
+    pub async fn parse(&self, db: &Database, tokens: &[DatabaseErc20Token]) -> Result<()> {
+        if tokens.is_empty() {
+            return Ok(());
+        }
+
+        let ids: Vec<(String, String)> = tokens
+            .iter()
+            .map(|token| (token.address.clone(), token.chain.clone()))
+            .collect();
+
+        let uncurated: Vec<(String, String)> = tokens
+            .iter()
+            .filter(|token| token.trust != TOKEN_TRUST_CURATED)
+            .map(|token| (token.address.clone(), token.chain.clone()))
+            .collect();
+
+        let airdrops = self.get_max_receivers(db, &uncurated).await?;
+
+        let verified = self.get_verified_contracts(db, &ids).await?;
+
+        let fake_senders = self.get_fake_senders(db, &uncurated).await?;
+
+        let mut db_flags: Vec<DatabaseTokenFlag> = Vec::new();
+
+        for token in tokens {
+            let id = (token.address.clone(), token.chain.clone());
+
+            let mut reasons: Vec<(&str, i64)> = Vec::new();
+
+            if airdrops.get(&id).copied().unwrap_or_default() >= AIRDROP_RECEIVERS {
+                reasons.push(REASON_AIRDROP);
+            }
+
+            let has_url = [&token.name, &token.symbol]
+                .iter()
+                .filter_map(|text| text.as_ref())
+                .any(|text| {
+                    let text = text.to_lowercase();
+
+                    URL_PATTERNS.iter().any(|pattern| text.contains(pattern))
+                });
+
+            if has_url {
+                reasons.push(REASON_URL_NAME);
+            }
+
+            if token.trust == TOKEN_TRUST_UNVERIFIED {
+                reasons.push(REASON_NOT_LISTED);
+            }
+
+            if verified.get(&id) == Some(&false) {
+                reasons.push(REASON_UNVERIFIED_CONTRACT);
+            }
+
+            if fake_senders.get(&id).copied().unwrap_or_default() >= FAKE_TRANSFERS_SENDERS {
+                reasons.push(REASON_FAKE_TRANSFERS);
+            }
+
+            let score: i64 = reasons.iter().map(|(_, weight)| weight).sum();
+
+            db_flags.push(DatabaseTokenFlag {
+                chain: token.chain.clone(),
+                reasons: reasons
+                    .iter()
+                    .map(|(reason, _)| reason.to_string())
+                    .collect(),
+                score,
+                spam: score >= SPAM_SCORE_THRESHOLD && token.trust != TOKEN_TRUST_CURATED,
+                token: token.address.clone(),
+            });
+        }
+
+        self.store_flags(db, &db_flags).await?;
+
+        info!(
+            "TokenFlags: Classified {} tokens, {} flagged as spam.",
+            db_flags.len(),
+            db_flags.iter().filter(|flag| flag.spam).count()
+        );
+
+        Ok(())
+    }
+
+    /// Returns the largest amount of receivers of a single sender in one transaction that
+    /// never received the token before, within the last `AIRDROP_BLOCKS` of each chain.
+    async fn get_max_receivers(
+        &self,
+        db: &Database,
+        tokens: &[(String, String)],
+    ) -> Result<HashMap<(String, String), i64>> {
+        let connection = db.get_connection();
+
+        let mut receivers = HashMap::new();
+
+        let chains: HashSet<&String> = tokens.iter().map(|(_, chain)| chain).collect();
+
+        for chain in chains {
+            let latest_block = match self.get_latest_block(db, chain).await? {
+                Some(latest_block) => latest_block,
+                None => continue,
+            };
+
+            let chain_tokens: Vec<&(String, String)> = tokens
+                .iter()
+                .filter(|(_, token_chain)| token_chain == chain)
+                .collect();
+
+            let chunks = get_chunks(chain_tokens.len(), 2);
+
+            for (start, end) in chunks {
+                let mut query_builder = QueryBuilder::new(
+                    "SELECT token, chain, max(receivers) FROM (SELECT erc20_transfers.token, erc20_transfers.chain, count(DISTINCT erc20_transfers.to_address) AS receivers FROM erc20_transfers INNER JOIN transactions ON erc20_transfers.hash = transactions.hash WHERE transactions.block_number >= ",
+                );
+
+                query_builder
+                    .push_bind(latest_block - AIRDROP_BLOCKS)
+                    .push(" AND (erc20_transfers.token, erc20_transfers.chain) IN ");
+
+                query_builder.push_tuples(&chain_tokens[start..end], |mut row, (token, chain)| {
+                    row.push_bind(token.clone()).push_bind(chain.clone());
+                });
+
+                query_builder.push(" AND NOT EXISTS (SELECT 1 FROM erc20_transfers AS previous INNER JOIN transactions AS previous_transactions ON previous.hash = previous_transactions.hash WHERE previous.token = erc20_transfers.token AND previous.chain = erc20_transfers.chain AND previous.to_address = erc20_transfers.to_address AND previous_transactions.block_number < transactions.block_number) GROUP BY erc20_transfers.token, erc20_transfers.chain, erc20_transfers.hash, erc20_transfers.from_address) AS senders GROUP BY token, chain");
+
+                let rows = query_builder
+                    .build_query_as::<(String, String, i64)>()
+                    .fetch_all(connection)
+                    .await?;
+
+                for (token, chain, amount) in rows {
+                    receivers.insert((token, chain), amount);
+                }
+            }
+        }
+
+        Ok(receivers)
+    }
+
+    async fn get_latest_block(&self, db: &Database, chain: &str) -> Result<Option<i64>> {
+        let connection = db.get_connection();
+
+        let block = sqlx::query_scalar::<_, i64>(
+            "SELECT number FROM blocks WHERE chain = $1 ORDER BY number DESC LIMIT 1",
+        )
+        .bind(chain.to_owned())
+        .fetch_optional(connection)
+        .await?;
+
+        Ok(block)
+    }
+
+    async fn get_verified_contracts(
+        &self,
+        db: &Database,
+        tokens: &[(String, String)],
+    ) -> Result<HashMap<(String, String), bool>> {
+        let connection = db.get_connection();
+
+        let mut verified = HashMap::new();
+
+        let chunks = get_chunks(tokens.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "SELECT contract, chain, verified FROM contracts_information WHERE (contract, chain) IN ",
+            );
+
+            query_builder.push_tuples(&tokens[start..end], |mut row, (token, chain)| {
+                row.push_bind(token.clone()).push_bind(chain.clone());
+            });
+
+            let rows = query_builder
+                .build_query_as::<(String, String, bool)>()
+                .fetch_all(connection)
+                .await?;
+
+            for (contract, chain, is_verified) in rows {
+                verified.insert((contract, chain), is_verified);
+            }
+        }
+
+        Ok(verified)
+    }
+
+    /// Returns the amount of senders that never received the token. Only tokens created in the
+    /// indexed blocks are counted, otherwise the balances received before are unknown.
+    async fn get_fake_senders(
+        &self,
+        db: &Database,
+        tokens: &[(String, String)],
+    ) -> Result<HashMap<(String, String), i64>> {
+        let mut senders = HashMap::new();
+
+        if tokens.is_empty() {
+            return Ok(senders);
+        }
+
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(tokens.len(), 2);
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "SELECT erc20_transfers.token, erc20_transfers.chain, count(DISTINCT erc20_transfers.from_address) FROM erc20_transfers INNER JOIN contracts ON erc20_transfers.token = contracts.contract AND erc20_transfers.chain = contracts.chain WHERE erc20_transfers.from_address != ",
+            );
+
+            query_builder
+                .push_bind(format_address(H160::zero()))
+                .push(" AND (erc20_transfers.token, erc20_transfers.chain) IN ");
+
+            query_builder.push_tuples(&tokens[start..end], |mut row, (token, chain)| {
+                row.push_bind(token.clone()).push_bind(chain.clone());
+            });
+
+            query_builder.push(" AND NOT EXISTS (SELECT 1 FROM erc20_transfers AS received WHERE received.token = erc20_transfers.token AND received.chain = erc20_transfers.chain AND received.to_address = erc20_transfers.from_address) GROUP BY erc20_transfers.token, erc20_transfers.chain");
+
+            let rows = query_builder
+                .build_query_as::<(String, String, i64)>()
+                .fetch_all(connection)
+                .await?;
+
+            for (token, chain, amount) in rows {
+                senders.insert((token, chain), amount);
+            }
+        }
+
+        Ok(senders)
+    }
+
+    async fn store_flags(&self, db: &Database, flags: &[DatabaseTokenFlag]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(flags.len(), DatabaseTokenFlag::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "UPSERT INTO token_flags (chain, reasons, score, spam, token, updated_at) ",
+            );
+
+            query_builder.push_values(&flags[start..end], |mut row, flag| {
+                row.push_bind(flag.chain.clone())
+                    .push_bind(flag.reasons.clone())
+                    .push_bind(flag.score)
+                    .push_bind(flag.spam)
+                    .push_bind(flag.token.clone())
+                    .push("now()");
+            });
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store token flags into database");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,606 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    chains::chains::CHAINS,
+    db::{
+        db::{get_chunks, Database},
+        models::models::DatabaseLog,
+    },
+    utils::format_number,
+};
+use anyhow::Result;
+use ethabi::{ParamType, Token};
+use ethers::{
+    types::{I256, U256},
+    utils::keccak256,
+};
+use field_count::FieldCount;
+use log::info;
+use serde::{Deserialize, Serialize};
+use sqlx::QueryBuilder;
+
+use super::{
+    dex_trades::{DexTrades, UNISWAP_V2},
+    erc20_balances::ERC20Balances,
+};
+
+/// Minimum value of the anchor side of a pool for its price to be used, small pools are
+/// trivial to manipulate.
+pub const MIN_ANCHOR_LIQUIDITY_USD: f64 = 10_000.0;
+
+/// Prices are stored in buckets of one hour, keeping the latest price of each bucket.
+pub const PRICE_BUCKET_SECONDS: i64 = 3600;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DatabaseLogWithTimestamp {
+    #[sqlx(flatten)]
+    pub log: DatabaseLog,
+    pub block_number: i64,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, FieldCount, sqlx::FromRow)]
+pub struct DatabaseTokenPrice {
+    pub block_number: i64,
+    pub chain: String,
+    pub hour: i64,
+    pub liquidity: Option<f64>,
+    pub log_index: i64,
+    pub price: f64,
+    pub source: String,
+    pub source_address: String,
+    pub token: String,
+}
+
+/// Chainlink aggregator emitting `AnswerUpdated` events with the USD price of a token.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceFeed {
+    pub aggregator: String,
+    pub chain: String,
+    pub decimals: i32,
+    pub token: String,
+}
+
+/// USD pegged token used as the anchor of the pools prices.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceAnchor {
+    pub chain: String,
+    pub token: String,
+}
+
+/// Pool reserves read from a `Sync` log.
+struct ReservesSample<'a> {
+    log: &'a DatabaseLogWithTimestamp,
+    hour: i64,
+    token0: String,
+    token1: String,
+    reserve0: U256,
+    reserve1: U256,
+}
+
+pub struct TokenPrices {
+    pub feeds: Vec<PriceFeed>,
+    pub anchors: Vec<PriceAnchor>,
+}
+
+impl TokenPrices {
+    /// Loads the Chainlink feeds from a JSON file with a list of `PriceFeed`.
+    pub fn load_feeds(path: &Option<String>) -> Vec<PriceFeed> {
+        let path = match path {
+            Some(path) => path,
+            None => return Vec::new(),
+        };
+
+        let file = std::fs::read_to_string(path).expect("Unable to read price feeds file.");
+
+        let feeds: Vec<PriceFeed> =
+            serde_json::from_str(&file).expect("Unable to parse price feeds file.");
+
+        feeds
+            .into_iter()
+            .map(|feed| PriceFeed {
+                aggregator: feed.aggregator.to_lowercase(),
+                chain: feed.chain,
+                decimals: feed.decimals,
+                token: feed.token.to_lowercase(),
+            })
+            .collect()
+    }
+
+    /// Loads the anchors from a JSON file with a list of `PriceAnchor`, or uses the stablecoins
+    /// of each chain when no file is provided.
+    pub fn load_anchors(path: &Option<String>) -> Vec<PriceAnchor> {
+        let path = match path {
+            Some(path) => path,
+            None => {
+                return CHAINS
+                    .iter()
+                    .flat_map(|chain| {
+                        chain.stablecoins.iter().map(|address| PriceAnchor {
+                            chain: chain.name.to_owned(),
+                            token: address.to_string(),
+                        })
+                    })
+                    .collect()
+            }
+        };
+
+        let file = std::fs::read_to_string(path).expect("Unable to read price anchors file.");
+
+        let anchors: Vec<PriceAnchor> =
+            serde_json::from_str(&file).expect("Unable to parse price anchors file.");
+
+        anchors
+            .into_iter()
+            .map(|anchor| PriceAnchor {
+                chain: anchor.chain,
+                token: anchor.token.to_lowercase(),
+            })
+            .collect()
+    }
+
+    pub async fn fetch(&self, db: &Database) -> Result<Vec<DatabaseLogWithTimestamp>> {
+        let connection = db.get_connection();
+
+        let rows = sqlx::query_as::<_, DatabaseLogWithTimestamp>(
+            "SELECT logs.*, transactions.block_number, transactions.timestamp FROM logs INNER JOIN transactions ON logs.hash = transactions.hash WHERE logs.token_prices_parsed = false LIMIT 500",
+        )
+        .fetch_all(connection)
+        .await;
+
+        match rows {
+            Ok(logs) => Ok(logs),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn parse(&self, db: &Database, logs: &[DatabaseLogWithTimestamp]) -> Result<()> {
+        let sync_topic = format!("0x{}", hex::encode(keccak256("Sync(uint112,uint112)")));
+
+        let answer_updated_topic = format!(
+            "0x{}",
+            hex::encode(keccak256("AnswerUpdated(int256,uint256,uint256)"))
+        );
+
+        let feeds: HashMap<(String, String), &PriceFeed> = self
+            .feeds
+            .iter()
+            .map(|feed| ((feed.aggregator.clone(), feed.chain.clone()), feed))
+            .collect();
+
+        let mut prices: HashMap<(String, String, i64, String), DatabaseTokenPrice> = HashMap::new();
+
+        let mut sync_logs: Vec<&DatabaseLogWithTimestamp> = Vec::new();
+
+        let mut db_parsed_logs: Vec<DatabaseLog> = Vec::new();
+
+        for log_with_timestamp in logs {
+            let log = &log_with_timestamp.log;
+
+            db_parsed_logs.push(log.to_owned());
+
+            let topic = match log.topics.first() {
+                Some(Some(topic)) => topic,
+                _ => continue,
+            };
+
+            if *topic == sync_topic && log.topics.len() == 1 {
+                sync_logs.push(log_with_timestamp);
+            }
+
+            if *topic == answer_updated_topic && log.topics.len() == 3 {
+                if let Some(feed) = feeds.get(&(log.address.clone(), log.chain.clone())) {
+                    if let Some(price) = self.parse_answer_updated(log_with_timestamp, feed) {
+                        insert_latest(&mut prices, price);
+                    }
+                }
+            }
+        }
+
+        for price in self.parse_reserves(db, &sync_logs).await? {
+            insert_latest(&mut prices, price);
+        }
+
+        let db_prices: Vec<DatabaseTokenPrice> = prices.into_values().collect();
+
+        if !db_prices.is_empty() {
+            self.store_prices(db, &db_prices).await?;
+        }
+
+        info!(
+            "TokenPrices: Inserted {} token prices to the database.",
+            db_prices.len()
+        );
+
+        if !db_parsed_logs.is_empty() {
+            db.update_logs_parsed("token_prices_parsed", &db_parsed_logs)
+                .await
+                .unwrap();
+        }
+
+        Ok(())
+    }
+
+    fn parse_answer_updated(
+        &self,
+        log_with_timestamp: &DatabaseLogWithTimestamp,
+        feed: &PriceFeed,
+    ) -> Option<DatabaseTokenPrice> {
+        let log = &log_with_timestamp.log;
+
+        let current = match log.topics.get(1) {
+            Some(Some(topic)) => hex::decode(topic.trim_start_matches("0x")).ok()?,
+            _ => return None,
+        };
+
+        let answer = match ethabi::decode(&[ParamType::Int(256)], &current)
+            .ok()?
+            .first()
+        {
+            Some(Token::Int(answer)) => I256::from_raw(*answer),
+            _ => return None,
+        };
+
+        if !answer.is_positive() {
+            return None;
+        }
+
+        let price = to_amount(answer.into_raw(), feed.decimals);
+
+        Some(DatabaseTokenPrice {
+            block_number: log_with_timestamp.block_number,
+            chain: log.chain.clone(),
+            hour: get_hour(&log_with_timestamp.timestamp)?,
+            liquidity: None,
+            log_index: log.log_index,
+            price,
+            source: String::from("chainlink"),
+            source_address: log.address.clone(),
+            token: feed.token.clone(),
+        })
+    }
+
+    /// Prices the tokens of the pools paired with a stablecoin first, which includes the
+    /// wrapped native token of each chain, and then the tokens paired with the wrapped native.
+    async fn parse_reserves(
+        &self,
+        db: &Database,
+        sync_logs: &[&DatabaseLogWithTimestamp],
+    ) -> Result<Vec<DatabaseTokenPrice>> {
+        let pools_keys: Vec<(String, String)> = sync_logs
+            .iter()
+            .map(|log| (log.log.address.clone(), log.log.chain.clone()))
+            .collect::<HashSet<(String, String)>>()
+            .into_iter()
+            .collect();
+
+        let dex_trades = DexTrades {};
+
+        let mut pools = dex_trades.get_pools(db, &pools_keys).await?;
+
+        // Pools not yet stored by the dex trades parser are resolved here, otherwise their
+        // reserves would be lost once the logs are marked as parsed.
+        let unknown_pools: Vec<((String, String), &'static str)> = pools_keys
+            .into_iter()
+            .filter(|(address, chain)| {
+                !pools
+                    .iter()
+                    .any(|pool| pool.address == *address && pool.chain == *chain)
+            })
+            .map(|pool| (pool, UNISWAP_V2))
+            .collect();
+
+        if !unknown_pools.is_empty() {
+            let resolved_pools = dex_trades.resolve_pools(db, unknown_pools).await?;
+
+            if !resolved_pools.is_empty() {
+                dex_trades.store_pools(db, &resolved_pools).await?;
+            }
+
+            pools.extend(resolved_pools);
+        }
+
+        let pools: HashMap<(String, String), (String, String)> = pools
+            .into_iter()
+            .map(|pool| ((pool.address, pool.chain), (pool.token0, pool.token1)))
+            .collect();
+
+        let mut samples: Vec<ReservesSample> = Vec::new();
+
+        for log_with_timestamp in sync_logs {
+            let log = &log_with_timestamp.log;
+
+            // Contracts emitting `Sync` that are not pools are skipped.
+            let (token0, token1) = match pools.get(&(log.address.clone(), log.chain.clone())) {
+                Some(tokens) => tokens.clone(),
+                None => continue,
+            };
+
+            let data = match hex::decode(log.data.trim_start_matches("0x")) {
+                Ok(data) => data,
+                Err(_) => continue,
+            };
+
+            let reserves =
+                match ethabi::decode(&[ParamType::Uint(112), ParamType::Uint(112)], &data) {
+                    Ok(reserves) => reserves,
+                    Err(_) => continue,
+                };
+
+            let (reserve0, reserve1) = match (&reserves[0], &reserves[1]) {
+                (Token::Uint(reserve0), Token::Uint(reserve1)) => (*reserve0, *reserve1),
+                _ => continue,
+            };
+
+            let hour = match get_hour(&log_with_timestamp.timestamp) {
+                Some(hour) => hour,
+                None => continue,
+            };
+
+            samples.push(ReservesSample {
+                log: log_with_timestamp,
+                hour,
+                token0,
+                token1,
+                reserve0,
+                reserve1,
+            });
+        }
+
+        let tokens: Vec<(String, String)> = samples
+            .iter()
+            .flat_map(|sample| {
+                [
+                    (sample.token0.clone(), sample.log.log.chain.clone()),
+                    (sample.token1.clone(), sample.log.log.chain.clone()),
+                ]
+            })
+            .collect::<HashSet<(String, String)>>()
+            .into_iter()
+            .collect();
+
+        let decimals: HashMap<(String, String), i64> = ERC20Balances {}
+            .get_tokens(db, &tokens)
+            .await
+            .into_iter()
+            .filter_map(|token| Some(((token.address, token.chain), token.decimals?)))
+            .collect();
+
+        let stablecoins: HashSet<(String, String)> = self
+            .anchors
+            .iter()
+            .map(|anchor| (anchor.token.clone(), anchor.chain.clone()))
+            .collect();
+
+        let wrapped_natives: HashMap<String, String> = CHAINS
+            .iter()
+            .filter_map(|chain| {
+                chain
+                    .wrapped_native
+                    .map(|address| (chain.name.to_owned(), address.to_owned()))
+            })
+            .collect();
+
+        let mut db_prices: Vec<DatabaseTokenPrice> = Vec::new();
+
+        let mut native_prices: HashMap<(String, i64), f64> = HashMap::new();
+
+        let mut native_samples: Vec<&ReservesSample> = Vec::new();
+
+        for sample in samples.iter() {
+            let chain = &sample.log.log.chain;
+
+            let is_stable0 = stablecoins.contains(&(sample.token0.clone(), chain.clone()));
+            let is_stable1 = stablecoins.contains(&(sample.token1.clone(), chain.clone()));
+
+            if is_stable0 == is_stable1 {
+                native_samples.push(sample);
+                continue;
+            }
+
+            if let Some(price) = self.get_sample_price(sample, is_stable0, 1.0, &decimals) {
+                if wrapped_natives.get(chain) == Some(&price.token) {
+                    native_prices.insert((chain.clone(), price.hour), price.price);
+                }
+
+                db_prices.push(price);
+            }
+        }
+
+        let mut latest_native_prices: HashMap<(String, i64), Option<f64>> = HashMap::new();
+
+        for sample in native_samples {
+            let chain = &sample.log.log.chain;
+
+            let wrapped_native = match wrapped_natives.get(chain) {
+                Some(wrapped_native) => wrapped_native,
+                None => continue,
+            };
+
+            let is_native0 = sample.token0 == *wrapped_native;
+            let is_native1 = sample.token1 == *wrapped_native;
+
+            if is_native0 == is_native1 {
+                continue;
+            }
+
+            let native_price = match native_prices.get(&(chain.clone(), sample.hour)) {
+                Some(native_price) => *native_price,
+                None => {
+                    let key = (chain.clone(), sample.hour);
+
+                    if !latest_native_prices.contains_key(&key) {
+                        let latest = self
+                            .get_latest_price(db, wrapped_native, chain, sample.hour)
+                            .await;
+
+                        latest_native_prices.insert(key.clone(), latest);
+                    }
+
+                    match latest_native_prices.get(&key) {
+                        Some(Some(native_price)) => *native_price,
+                        _ => continue,
+                    }
+                }
+            };
+
+            if let Some(price) = self.get_sample_price(sample, is_native0, native_price, &decimals)
+            {
+                db_prices.push(price);
+            }
+        }
+
+        Ok(db_prices)
+    }
+
+    /// Returns the price of the pool token that is not the anchor.
+    fn get_sample_price(
+        &self,
+        sample: &ReservesSample,
+        anchor_is_token0: bool,
+        anchor_price: f64,
+        decimals: &HashMap<(String, String), i64>,
+    ) -> Option<DatabaseTokenPrice> {
+        let chain = &sample.log.log.chain;
+
+        let (token, reserve, anchor, anchor_reserve) = if anchor_is_token0 {
+            (
+                &sample.token1,
+                sample.reserve1,
+                &sample.token0,
+                sample.reserve0,
+            )
+        } else {
+            (
+                &sample.token0,
+                sample.reserve0,
+                &sample.token1,
+                sample.reserve1,
+            )
+        };
+
+        let amount = to_amount(
+            reserve,
+            *decimals.get(&(token.clone(), chain.clone()))? as i32,
+        );
+
+        let anchor_amount = to_amount(
+            anchor_reserve,
+            *decimals.get(&(anchor.clone(), chain.clone()))? as i32,
+        );
+
+        if amount == 0.0 || anchor_amount * anchor_price < MIN_ANCHOR_LIQUIDITY_USD {
+            return None;
+        }
+
+        Some(DatabaseTokenPrice {
+            block_number: sample.log.block_number,
+            chain: chain.clone(),
+            hour: sample.hour,
+            liquidity: Some(anchor_amount * anchor_price),
+            log_index: sample.log.log.log_index,
+            price: anchor_amount / amount * anchor_price,
+            source: String::from("dex"),
+            source_address: sample.log.log.address.clone(),
+            token: token.clone(),
+        })
+    }
+
+    /// Returns the latest price of a token up to the given hour.
+    pub async fn get_latest_price(
+        &self,
+        db: &Database,
+        token: &str,
+        chain: &str,
+        hour: i64,
+    ) -> Option<f64> {
+        let connection = db.get_connection();
+
+        let row = sqlx::query_as::<_, DatabaseTokenPrice>(
+            "SELECT * FROM token_prices WHERE token = $1 AND chain = $2 AND hour <= $3 ORDER BY hour DESC, block_number DESC LIMIT 1",
+        )
+        .bind(token)
+        .bind(chain)
+        .bind(hour)
+        .fetch_optional(connection)
+        .await;
+
+        match row {
+            Ok(Some(price)) => Some(price.price),
+            _ => None,
+        }
+    }
+
+    async fn store_prices(&self, db: &Database, prices: &[DatabaseTokenPrice]) -> Result<()> {
+        let connection = db.get_connection();
+
+        let chunks = get_chunks(prices.len(), DatabaseTokenPrice::field_count());
+
+        for (start, end) in chunks {
+            let mut query_builder = QueryBuilder::new(
+                "INSERT INTO token_prices (block_number, chain, hour, liquidity, log_index, price, source, source_address, token) ",
+            );
+
+            query_builder.push_values(&prices[start..end], |mut row, price| {
+                row.push_bind(price.block_number)
+                    .push_bind(price.chain.clone())
+                    .push_bind(price.hour)
+                    .push_bind(price.liquidity)
+                    .push_bind(price.log_index)
+                    .push_bind(price.price)
+                    .push_bind(price.source.clone())
+                    .push_bind(price.source_address.clone())
+                    .push_bind(price.token.clone());
+            });
+
+            // Logs are not parsed in block order, each hour keeps the latest price of the most
+            // liquid pool.
+            query_builder.push(" ON CONFLICT (token, chain, hour, source) DO UPDATE SET block_number = excluded.block_number, liquidity = excluded.liquidity, log_index = excluded.log_index, price = excluded.price, source_address = excluded.source_address WHERE (token_prices.source_address = excluded.source_address AND (token_prices.block_number, token_prices.log_index) < (excluded.block_number, excluded.log_index)) OR (token_prices.source_address != excluded.source_address AND excluded.liquidity > token_prices.liquidity)");
+
+            let query = query_builder.build();
+
+            query
+                .execute(connection)
+                .await
+                .expect("Unable to store token prices into database");
+        }
+
+        Ok(())
+    }
+}
+
+fn insert_latest(
+    prices: &mut HashMap<(String, String, i64, String), DatabaseTokenPrice>,
+    price: DatabaseTokenPrice,
+) {
+    let id = (
+        price.token.clone(),
+        price.chain.clone(),
+        price.hour,
+        price.source.clone(),
+    );
+
+    let is_latest = match prices.get(&id) {
+        Some(stored) if stored.source_address == price.source_address => {
+            (stored.block_number, stored.log_index) < (price.block_number, price.log_index)
+        }
+        Some(stored) => price.liquidity > stored.liquidity,
+        None => true,
+    };
+
+    if is_latest {
+        prices.insert(id, price);
+    }
+}
+
+fn get_hour(timestamp: &str) -> Option<i64> {
+    let timestamp = timestamp.parse::<i64>().ok()?;
+
+    Some(timestamp - timestamp % PRICE_BUCKET_SECONDS)
+}
+
+fn to_amount(value: U256, decimals: i32) -> f64 {
+    let value: f64 = format_number(value).parse().unwrap_or_default();
+
+    value / 10f64.powi(decimals)
+}
@@ -1 +1,3 @@
+pub mod multicall;
+pub mod proxies;
 pub mod rpc;
@@ -0,0 +1,90 @@
+use anyhow::Result;
+use ethabi::{Address, ParamType, Token};
+use ethers::{
+    providers::{Http, HttpClientError, Middleware, Provider, ProviderError},
+    types::{transaction::eip2718::TypedTransaction, TransactionRequest},
+    utils::id,
+};
+
+/// Multicall3 is deployed with the same address on every supported chain.
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+#[derive(Debug, Clone)]
+pub struct Call {
+    pub target: Address,
+    pub data: Vec<u8>,
+}
+
+/// Returns whether the `aggregate3` call was executed and reverted or ran out of gas, as
+/// opposed to the RPC being unreachable or rejecting the request.
+pub fn is_execution_error(error: &anyhow::Error) -> bool {
+    let error = match error.downcast_ref::<ProviderError>() {
+        Some(ProviderError::JsonRpcClientError(error)) => error,
+        _ => return false,
+    };
+
+    match error.downcast_ref::<HttpClientError>() {
+        Some(HttpClientError::JsonRpcError(error)) => {
+            let message = error.message.to_lowercase();
+
+            message.contains("revert") || message.contains("gas")
+        }
+        _ => false,
+    }
+}
+
+/// Executes all the calls in a single `eth_call` with `aggregate3`, allowing each call to
+/// fail. Returns the data of each call, `None` for the calls that reverted.
+pub async fn aggregate3(provider: &Provider<Http>, calls: &[Call]) -> Result<Vec<Option<Vec<u8>>>> {
+    let tokens = vec![Token::Array(
+        calls
+            .iter()
+            .map(|call| {
+                Token::Tuple(vec![
+                    Token::Address(call.target),
+                    Token::Bool(true),
+                    Token::Bytes(call.data.clone()),
+                ])
+            })
+            .collect(),
+    )];
+
+    let mut data = id("aggregate3((address,bool,bytes)[])").to_vec();
+
+    data.extend(ethabi::encode(&tokens));
+
+    let transaction: TypedTransaction = TransactionRequest::new()
+        .to(MULTICALL3_ADDRESS.parse::<Address>()?)
+        .data(data)
+        .into();
+
+    let output = provider.call(&transaction, None).await?;
+
+    let decoded = ethabi::decode(
+        &[ParamType::Array(Box::new(ParamType::Tuple(vec![
+            ParamType::Bool,
+            ParamType::Bytes,
+        ])))],
+        &output,
+    )?;
+
+    let results = match decoded.into_iter().next() {
+        Some(Token::Array(results)) => results,
+        _ => return Err(anyhow::anyhow!("Invalid aggregate3 response")),
+    };
+
+    if results.len() != calls.len() {
+        return Err(anyhow::anyhow!("Invalid aggregate3 response length"));
+    }
+
+    Ok(results
+        .into_iter()
+        .map(|result| match result {
+            Token::Tuple(values) => match (&values[0], &values[1]) {
+                (Token::Bool(true), Token::Bytes(data)) => Some(data.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect())
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use ethabi::Address;
+use ethers::{
+    providers::{Http, Middleware, Provider},
+    types::{transaction::eip2718::TypedTransaction, BlockId, TransactionRequest, H256, U256},
+    utils::{id, keccak256},
+};
+
+pub const PROXY_STANDARD_EIP1967: &str = "eip1967";
+pub const PROXY_STANDARD_EIP1967_BEACON: &str = "eip1967-beacon";
+pub const PROXY_STANDARD_EIP1822: &str = "eip1822";
+pub const PROXY_STANDARD_OPENZEPPELIN: &str = "openzeppelin";
+pub const PROXY_STANDARD_EIP897: &str = "eip897";
+pub const PROXY_STANDARD_EXPLORER: &str = "explorer";
+pub const PROXY_STANDARD_UPGRADED_EVENT: &str = "upgraded-event";
+
+/// Storage slot defined by a label, EIP-1967 slots are the hash minus one to avoid a known
+/// preimage.
+pub fn get_slot(label: &str, minus_one: bool) -> H256 {
+    let slot = U256::from_big_endian(&keccak256(label.as_bytes()));
+
+    let slot = if minus_one { slot - 1 } else { slot };
+
+    let mut bytes = [0u8; 32];
+
+    slot.to_big_endian(&mut bytes);
+
+    H256::from(bytes)
+}
+
+/// Returns the implementation of a proxy and the standard used to find it. Storage slots are
+/// checked first, `implementation()` is only called when no slot is set, as any contract
+/// can expose a function with the same name.
+pub async fn get_implementation(
+    provider: &Provider<Http>,
+    address: Address,
+    block: Option<BlockId>,
+) -> Result<Option<(Address, &'static str)>> {
+    let slots = [
+        (
+            get_slot("eip1967.proxy.implementation", true),
+            PROXY_STANDARD_EIP1967,
+        ),
+        (get_slot("PROXIABLE", false), PROXY_STANDARD_EIP1822),
+        (
+            get_slot("org.zeppelinos.proxy.implementation", false),
+            PROXY_STANDARD_OPENZEPPELIN,
+        ),
+    ];
+
+    for (slot, standard) in slots {
+        let value = provider.get_storage_at(address, slot, block).await?;
+
+        if let Some(implementation) = slot_to_address(value) {
+            return Ok(Some((implementation, standard)));
+        }
+    }
+
+    let beacon = provider
+        .get_storage_at(address, get_slot("eip1967.proxy.beacon", true), block)
+        .await?;
+
+    if let Some(beacon) = slot_to_address(beacon) {
+        if let Some(implementation) = call_implementation(provider, beacon, block).await {
+            return Ok(Some((implementation, PROXY_STANDARD_EIP1967_BEACON)));
+        }
+    }
+
+    match call_implementation(provider, address, block).await {
+        Some(implementation) if implementation != address => {
+            Ok(Some((implementation, PROXY_STANDARD_EIP897)))
+        }
+        _ => Ok(None),
+    }
+}
+
+async fn call_implementation(
+    provider: &Provider<Http>,
+    address: Address,
+    block: Option<BlockId>,
+) -> Option<Address> {
+    let transaction: TypedTransaction = TransactionRequest::new()
+        .to(address)
+        .data(id("implementation()").to_vec())
+        .into();
+
+    let output = provider.call(&transaction, block).await.ok()?;
+
+    if output.len() != 32 {
+        return None;
+    }
+
+    slot_to_address(H256::from_slice(&output))
+}
+
+fn slot_to_address(value: H256) -> Option<Address> {
+    // Values with data beyond the last 20 bytes are not addresses.
+    if value.as_bytes()[..12].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+
+    let address = Address::from_slice(&value.as_bytes()[12..]);
+
+    if address.is_zero() {
+        None
+    } else {
+        Some(address)
+    }
+}
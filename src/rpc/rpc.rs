@@ -4,18 +4,29 @@ use crate::{
     db::models::models::{
         DatabaseBlock, DatabaseContract, DatabaseLog, DatabaseReceipt, DatabaseTransaction,
     },
-    utils::format_small_number,
+    utils::{format_address, format_small_number},
 };
-use ethers::types::{Block, Transaction, TransactionReceipt, U256};
+use ethers::types::{Block, Bytes, Transaction, TransactionReceipt, H160, U256};
 
 use anyhow::Result;
-use jsonrpsee::core::{client::ClientT, rpc_params};
+use jsonrpsee::{
+    core::{client::ClientT, rpc_params, Error as ClientError},
+    types::error::CallError,
+};
 use jsonrpsee_http_client::{HttpClient, HttpClientBuilder};
 use log::info;
 use rand::seq::SliceRandom;
 use std::time::Duration;
 
-use serde_json::Error;
+use serde::Deserialize;
+use serde_json::{json, Error, Value};
+
+/// Top level frame of a `callTracer` trace.
+#[derive(Debug, Clone, Deserialize)]
+struct CallTrace {
+    error: Option<String>,
+    output: Option<Bytes>,
+}
 
 #[derive(Debug, Clone)]
 pub struct Rpc {
@@ -242,6 +253,71 @@ impl Rpc {
         }
     }
 
+    /// Returns the revert data and error message of a failed transaction. The data is taken
+    /// from the call trace when the node supports `debug_traceTransaction`, otherwise the call
+    /// is replayed with `eth_call` on top of the parent block.
+    pub async fn get_revert_data(
+        &self,
+        transaction: &DatabaseTransaction,
+    ) -> Result<Option<(Vec<u8>, Option<String>)>> {
+        let client = self.get_client();
+
+        let raw_trace = client
+            .request(
+                "debug_traceTransaction",
+                rpc_params![transaction.hash.clone(), json!({ "tracer": "callTracer" })],
+            )
+            .await;
+
+        if let Ok(value) = raw_trace {
+            let trace: Result<CallTrace, Error> = serde_json::from_value(value);
+
+            if let Ok(trace) = trace {
+                let data = match trace.output {
+                    Some(output) => output.to_vec(),
+                    None => Vec::new(),
+                };
+
+                return Ok(Some((data, trace.error)));
+            }
+        }
+
+        let mut call = json!({
+            "from": transaction.from_address,
+            "data": transaction.input,
+            "gas": format!("0x{:x}", U256::from_dec_str(&transaction.gas)?),
+            "value": format!("0x{:x}", U256::from_dec_str(&transaction.value)?),
+        });
+
+        // Contract creations are stored with the zero address as receiver.
+        if transaction.to_address != format_address(H160::zero()) {
+            call["to"] = json!(transaction.to_address);
+        }
+
+        let parent_block = format!("0x{:x}", transaction.block_number - 1);
+
+        let raw_call: Result<Value, ClientError> = client
+            .request("eth_call", rpc_params![call, parent_block])
+            .await;
+
+        match raw_call {
+            // The call doesn't revert anymore with the state of the parent block.
+            Ok(_) => Ok(None),
+            Err(ClientError::Call(CallError::Custom(error))) => {
+                let data: Vec<u8> = match error.data() {
+                    Some(data) => match serde_json::from_str::<Bytes>(data.get()) {
+                        Ok(data) => data.to_vec(),
+                        Err(_) => Vec::new(),
+                    },
+                    None => Vec::new(),
+                };
+
+                Ok(Some((data, Some(error.message().to_owned()))))
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
     fn get_client(&self) -> &HttpClient {
         let client = self.clients.choose(&mut rand::thread_rng()).unwrap();
         return client;
@@ -1,4 +1,6 @@
-use ethers::types::{Bytes, H160, H256, H64, U256, U64};
+use ethabi::{param_type::Reader, ParamType, Token};
+use ethers::types::{Bytes, H160, H256, H64, I256, U256, U64};
+use serde_json::{json, Value};
 
 pub fn format_nonce(h: H64) -> String {
     return format!("{:?}", h);
@@ -32,3 +34,38 @@ pub fn format_number(n: U256) -> String {
 pub fn format_small_number(n: U64) -> String {
     return format!("{}", n.to_string());
 }
+
+pub fn format_token(token: &Token) -> Value {
+    match token {
+        Token::Address(address) => json!(format_address(*address)),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => json!(format_bytes_slice(bytes)),
+        Token::Int(int) => json!(I256::from_raw(*int).to_string()),
+        Token::Uint(uint) => json!(format_number(*uint)),
+        Token::Bool(boolean) => json!(boolean),
+        Token::String(string) => json!(string),
+        Token::FixedArray(tokens) | Token::Array(tokens) | Token::Tuple(tokens) => {
+            Value::Array(tokens.iter().map(format_token).collect())
+        }
+    }
+}
+
+/// Parses a method signature like `transfer(address,uint256)` into its name and inputs.
+pub fn parse_signature(signature: &str) -> Option<(String, Vec<ParamType>)> {
+    let (name, inputs) = signature.split_once('(')?;
+
+    let inputs: String = inputs
+        .trim_end()
+        .strip_suffix(')')?
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    if inputs.is_empty() {
+        return Some((name.to_owned(), Vec::new()));
+    }
+
+    match Reader::read(&format!("({})", inputs)) {
+        Ok(ParamType::Tuple(params)) => Some((name.to_owned(), params)),
+        _ => None,
+    }
+}